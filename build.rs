@@ -0,0 +1,346 @@
+// With the `generate-enums` feature, generates the enums of `src/enums.rs`
+// from the kernel headers, so that they match the kernel built against
+// rather than the headers bundled with libevdev.
+//
+// Names defined as other names, e.g. `BTN_A` as `BTN_SOUTH`, and the starts
+// of ranges, e.g. `BTN_GAMEPAD`, are generated as associated constants and
+// listed in the `ALIASES` of the enums.
+//
+// The headers are looked up in `$EVDEV_INPUT_HEADERS/linux`, which defaults
+// to `/usr/include`, then in the libevdev submodule. The enums are written to
+// `$OUT_DIR/enums.rs`, and to `$EVDEV_ENUMS_OUT` as well if set, which
+// tools/make-enums.sh uses to update the bundled enums.
+
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+macro_rules! t {
+    ($e:expr) => (match $e {
+        Ok(t) => t,
+        Err(e) => panic!("{} return the error {}", stringify!($e), e),
+    })
+}
+
+const HEADERS: [&str; 2] = ["input-event-codes.h", "input.h"];
+
+const PREFIXES: [&str; 14] = [
+    "EV_",
+    "REL_",
+    "ABS_",
+    "KEY_",
+    "BTN_",
+    "LED_",
+    "SND_",
+    "MSC_",
+    "SW_",
+    "FF_",
+    "SYN_",
+    "REP_",
+    "INPUT_PROP_",
+    "BUS_",
+];
+
+/// Defines which are not codes.
+const SKIPPED: [&str; 1] = ["EV_VERSION"];
+
+/// The starts of ranges of codes, which are only aliases of the codes
+/// defined with the same values.
+const RANGES: [&str; 7] = [
+    "BTN_MISC",
+    "BTN_MOUSE",
+    "BTN_JOYSTICK",
+    "BTN_GAMEPAD",
+    "BTN_DIGI",
+    "BTN_WHEEL",
+    "BTN_TRIGGER_HAPPY",
+];
+
+/// The prefixes of the event types with an enum of codes.
+const EVENT_NAMES: [&str; 11] = [
+    "REL_", "ABS_", "KEY_", "BTN_", "LED_", "SND_", "MSC_", "SW_", "FF_", "SYN_", "REP_",
+];
+
+/// The names defined for a value.
+type Names = (u32, Vec<String>);
+
+/// The names defined for each value, in the order of the header, for each
+/// prefix.
+#[derive(Default)]
+struct Bits {
+    prefixes: Vec<(String, Vec<Names>)>,
+}
+
+impl Bits {
+    fn get(&self, prefix: &str) -> Option<&[Names]> {
+        self.prefixes.iter()
+            .find(|(p, _)| p == prefix)
+            .map(|(_, values)| &values[..])
+    }
+
+    fn add(&mut self, prefix: &str, value: u32, name: &str) {
+        let index = match self.prefixes.iter().position(|(p, _)| p == prefix) {
+            Some(index) => index,
+            None => {
+                self.prefixes.push((prefix.to_string(), Vec::new()));
+                self.prefixes.len() - 1
+            },
+        };
+        let values = &mut self.prefixes[index].1;
+        match values.iter_mut().find(|(v, _)| *v == value) {
+            Some((_, names)) => names.push(name.to_string()),
+            None => values.push((value, vec![name.to_string()])),
+        }
+    }
+
+    /// Returns the value of a name defined before.
+    fn value_of(&self, name: &str) -> Option<u32> {
+        self.prefixes.iter()
+            .flat_map(|(_, values)| values)
+            .find(|(_, names)| names.iter().any(|n| n == name))
+            .map(|&(value, _)| value)
+    }
+
+    /// Returns `true` if a name is defined for `value`.
+    fn has_value(&self, prefix: &str, value: u32) -> bool {
+        self.get(prefix).is_some_and(|values| values.iter().any(|(v, _)| *v == value))
+    }
+}
+
+/// The value of a define, either a number or another define.
+enum Value<'a> {
+    Number(u32),
+    Name(&'a str),
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    if env::var_os("CARGO_FEATURE_GENERATE_ENUMS").is_none() {
+        return;
+    }
+    println!("cargo:rerun-if-env-changed=EVDEV_INPUT_HEADERS");
+
+    let dir = headers_dir();
+    let mut text = "/* THIS FILE IS GENERATED, DO NOT EDIT */\n\n".to_string();
+    for header in &HEADERS {
+        let path = dir.join(header);
+        println!("cargo:rerun-if-changed={}", path.display());
+        let bits = parse(&t!(fs::read_to_string(&path)));
+        print_mapping_table(&mut text, &bits);
+    }
+
+    let out = PathBuf::from(env::var_os("OUT_DIR").unwrap()).join("enums.rs");
+    t!(t!(fs::File::create(&out)).write_all(text.as_bytes()));
+
+    println!("cargo:rerun-if-env-changed=EVDEV_ENUMS_OUT");
+    if let Some(copy) = env::var_os("EVDEV_ENUMS_OUT") {
+        // Written again whenever it is changed, e.g. by a checkout.
+        println!("cargo:rerun-if-changed={}", Path::new(&copy).display());
+        t!(fs::write(&copy, &text));
+    }
+}
+
+fn headers_dir() -> PathBuf {
+    let dir = env::var_os("EVDEV_INPUT_HEADERS")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("/usr/include"))
+        .join("linux");
+    if dir.join(HEADERS[0]).exists() {
+        return dir;
+    }
+
+    let bundled = Path::new(&env::var_os("CARGO_MANIFEST_DIR").unwrap())
+        .join("evdev-sys/libevdev/include/linux");
+    if bundled.join(HEADERS[0]).exists() {
+        return bundled;
+    }
+    panic!("{} not found in {} nor {}, set EVDEV_INPUT_HEADERS",
+           HEADERS[0], dir.display(), bundled.display());
+}
+
+fn parse(header: &str) -> Bits {
+    let mut bits = Bits::default();
+    let mut ranges = Vec::new();
+    for line in header.lines() {
+        let (name, value) = match parse_define(line) {
+            Some(define) => define,
+            None => continue,
+        };
+        if SKIPPED.contains(&name) {
+            continue;
+        }
+        let value = match value {
+            Value::Number(value) => value,
+            Value::Name(other) => match bits.value_of(other) {
+                Some(value) => value,
+                None => continue,
+            },
+        };
+        for prefix in PREFIXES.iter().filter(|prefix| name.starts_with(*prefix)) {
+            let prefix = prefix[..prefix.len() - 1].to_lowercase();
+            if RANGES.contains(&name) {
+                // Defined before the first code of the range.
+                ranges.push((prefix, value, name));
+            } else {
+                bits.add(&prefix, value, name);
+            }
+        }
+    }
+
+    for (prefix, value, name) in ranges {
+        if bits.has_value(&prefix, value) {
+            bits.add(&prefix, value, name);
+        }
+    }
+    bits
+}
+
+/// Parse `#define NAME VALUE`, where the value is a number or a name.
+fn parse_define<'a>(line: &'a str) -> Option<(&'a str, Value<'a>)> {
+    let is_word = |c: char| c.is_ascii_alphanumeric() || c == '_';
+
+    let rest = line.strip_prefix("#define")?;
+    let name_start = rest.strip_prefix(char::is_whitespace)?.trim_start();
+    let name_len = name_start.find(|c| !is_word(c)).unwrap_or(name_start.len());
+    let (name, rest) = name_start.split_at(name_len);
+    let value = rest.strip_prefix(char::is_whitespace)?.trim_start();
+    let value = &value[..value.find(|c| !is_word(c)).unwrap_or(value.len())];
+    if name.is_empty() || value.is_empty() {
+        return None;
+    }
+
+    let value = match value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")) {
+        Some(hex) => Value::Number(u32::from_str_radix(hex, 16).ok()?),
+        None if value.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') => Value::Name(value),
+        // Leading zeroes are not decimal.
+        None if value.len() > 1 && value.starts_with('0') => return None,
+        None => Value::Number(value.parse().ok()?),
+    };
+    Some((name, value))
+}
+
+/// The name of the enum of the values with a prefix, and the suffix of its
+/// `int_to_` function.
+fn enum_name(prefix: &str) -> (String, String) {
+    match prefix {
+        "ev" => ("EventType".to_string(), "event_type".to_string()),
+        "input_prop" => ("InputProp".to_string(), "input_prop".to_string()),
+        "bus" => ("BusType".to_string(), "bus_type".to_string()),
+        _ => (format!("EV_{}", prefix.to_uppercase()), format!("ev_{}", prefix)),
+    }
+}
+
+/// The values of a prefix, followed by the buttons for keys.
+fn values<'a>(bits: &'a Bits, prefix: &str) -> Vec<&'a Names> {
+    let mut values: Vec<_> = bits.get(prefix).unwrap_or(&[]).iter().collect();
+    if prefix == "key" {
+        values.extend(bits.get("btn").unwrap_or(&[]));
+    }
+    values
+}
+
+fn print_enums(text: &mut String, bits: &Bits, prefix: &str) {
+    let (enum_name, _) = enum_name(prefix);
+    if bits.get(prefix).is_none() {
+        return;
+    }
+
+    let mut associated_names = Vec::new();
+    *text += "#[allow(non_camel_case_types)]\n";
+    *text += "#[derive(Clone, Debug, PartialEq, Eq, Hash)]\n";
+    *text += &format!("pub enum {} {{\n", enum_name);
+    for (value, names) in values(bits, prefix) {
+        // EV_MAX is used as a proxy to write the unknown event type.
+        if names[0] == "EV_MAX" {
+            *text += "    EV_UNK,\n";
+        }
+        *text += &format!("    {} = {},\n", names[0], value);
+        if names.len() > 1 {
+            associated_names.push((&names[0], &names[1..]));
+        }
+    }
+    *text += "}\n\n";
+
+    *text += &format!("impl {} {{\n", enum_name);
+    for &(orig, names) in &associated_names {
+        for name in names {
+            *text += &format!("    pub const {}: {} = {}::{};\n", name, enum_name, enum_name, orig);
+        }
+    }
+    if !associated_names.is_empty() {
+        *text += "\n";
+    }
+    *text += "    /// The other names of the values, with the values they stand for.\n";
+    *text += &format!("    pub const ALIASES: &'static [(&'static str, {})] = &[", enum_name);
+    if !associated_names.is_empty() {
+        *text += "\n";
+    }
+    for &(orig, names) in &associated_names {
+        for name in names {
+            *text += &format!("        (\"{}\", {}::{}),\n", name, enum_name, orig);
+        }
+    }
+    if !associated_names.is_empty() {
+        *text += "    ";
+    }
+    *text += "];\n";
+    *text += "}\n\n";
+}
+
+fn print_enums_convert_fn(text: &mut String, bits: &Bits, prefix: &str) {
+    let (enum_name, fn_name) = enum_name(prefix);
+    if bits.get(prefix).is_none() {
+        return;
+    }
+
+    *text += &format!("pub fn int_to_{}(code: u32) -> Option<{}> {{\n", fn_name, enum_name);
+    *text += "    match code {\n";
+    for (value, names) in values(bits, prefix) {
+        // EV_MAX is used as a proxy to write the unknown event type.
+        if names[0] == "EV_MAX" {
+            *text += &format!("        c if c < {} => Some(EventType::EV_UNK),\n", value);
+        }
+        *text += &format!("        {} => Some({}::{}),\n", value, enum_name, names[0]);
+    }
+    *text += "        _ => None\n";
+    *text += "    }\n";
+    *text += "}\n\n";
+}
+
+fn print_event_code(text: &mut String, bits: &Bits) {
+    let types = match bits.get("ev") {
+        Some(types) => types,
+        None => return,
+    };
+
+    *text += "#[allow(non_camel_case_types)]\n";
+    *text += "#[derive(Clone, Debug, PartialEq)]\n";
+    *text += "pub enum EventCode {\n";
+    for (_, names) in types {
+        let name = &names[0];
+        if EVENT_NAMES.contains(&&*format!("{}_", &name[3..])) {
+            *text += &format!("    {}({}),\n", name, name);
+        } else if name == "EV_FF_STATUS" {
+            *text += "    EV_FF_STATUS(EV_FF),\n";
+        } else {
+            // EV_MAX is used as a proxy to write the unknown event type.
+            if name == "EV_MAX" {
+                *text += "    EV_UNK { event_type: u32, event_code: u32 },\n";
+            }
+            *text += &format!("    {},\n", name);
+        }
+    }
+    *text += "}\n\n";
+}
+
+fn print_mapping_table(text: &mut String, bits: &Bits) {
+    for prefix in PREFIXES.iter().filter(|prefix| **prefix != "BTN_") {
+        let prefix = prefix[..prefix.len() - 1].to_lowercase();
+        print_enums(text, bits, &prefix);
+        print_enums_convert_fn(text, bits, &prefix);
+        if prefix == "ev" {
+            print_event_code(text, bits);
+        }
+    }
+}
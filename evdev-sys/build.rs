@@ -20,6 +20,7 @@ fn main() {
         for path in &lib.include_paths {
             println!("cargo:include={}", path.display());
         }
+        build_log_shim(&lib.include_paths);
         return
     }
 
@@ -68,6 +69,17 @@ fn main() {
     run(Command::new("make")
                 .arg("install")
                 .current_dir(&dst.join("build")));
+
+    build_log_shim(&[dst.join("include/libevdev-1.0")]);
+}
+
+/// Compile the C trampolines formatting libevdev's log messages.
+fn build_log_shim(include_paths: &[PathBuf]) {
+    println!("cargo:rerun-if-changed=src/log.c");
+    cc::Build::new()
+        .file("src/log.c")
+        .includes(include_paths)
+        .compile("evdev-sys-log");
 }
 
 fn run(cmd: &mut Command) {
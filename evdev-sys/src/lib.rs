@@ -38,22 +38,41 @@ pub const LIBEVDEV_UINPUT_OPEN_MANAGED: libevdev_uinput_open_mode = -2;
 pub enum libevdev {}
 pub enum libevdev_uinput {}
 
+/// Opaque stand-in for the C `va_list`, which cannot be read from Rust. Use
+/// the `evdev_sys_*` log functions to receive formatted messages instead.
 #[repr(C)]
 pub struct va_list {
-    // TODO
+    _private: [u8; 0],
 }
 
-type libevdev_log_func_t = extern fn(*const libevdev,
-                                     *mut c_void,
-                                     *const c_char, c_int,
-                                     *const c_char,
-                                     *const c_char, va_list);
+pub type libevdev_log_func_t = Option<extern fn(libevdev_log_priority,
+                                                *mut c_void,
+                                                *const c_char, c_int,
+                                                *const c_char,
+                                                *const c_char, *mut va_list)>;
 
-type libevdev_device_log_func_t = extern fn(*const libevdev,
-                                            c_int, *mut c_void,
-                                            *const c_char, c_int,
-                                            *const c_char,
-                                            *const c_char, va_list);
+pub type libevdev_device_log_func_t = Option<extern fn(*const libevdev,
+                                                       libevdev_log_priority,
+                                                       *mut c_void,
+                                                       *const c_char, c_int,
+                                                       *const c_char,
+                                                       *const c_char, *mut va_list)>;
+
+/// Receives a log message of libevdev, formatted by the C trampolines of
+/// `evdev_sys_set_log_function` and `evdev_sys_set_device_log_function`. `dev`
+/// is null for messages of the global handler.
+pub type evdev_sys_log_handler = extern fn(dev: *const libevdev,
+                                           priority: libevdev_log_priority,
+                                           data: *mut c_void,
+                                           file: *const c_char, line: c_int,
+                                           func: *const c_char,
+                                           message: *const c_char);
+
+#[repr(C)]
+pub struct evdev_sys_logger {
+    pub handler: evdev_sys_log_handler,
+    pub data: *mut c_void,
+}
 
 
 extern {
@@ -63,6 +82,10 @@ extern {
     pub fn libevdev_free(ctx: *mut libevdev);
     pub fn libevdev_set_log_function(logfunc: libevdev_log_func_t,
                                      data: *mut c_void);
+    pub fn evdev_sys_set_log_function(logger: *mut evdev_sys_logger);
+    pub fn evdev_sys_set_device_log_function(ctx: *mut libevdev,
+                                             logger: *mut evdev_sys_logger,
+                                             priority: libevdev_log_priority);
     pub fn libevdev_set_log_priority(priority: libevdev_log_priority);
     pub fn libevdev_get_log_priority() -> libevdev_log_priority;
     pub fn libevdev_set_device_log_function(ctx: *mut libevdev,
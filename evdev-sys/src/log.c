@@ -0,0 +1,86 @@
+/*
+ * libevdev passes log messages as a printf format and a va_list, which Rust
+ * cannot consume. These trampolines format the message and hand it over to
+ * the handler of an evdev_sys_logger as a plain string.
+ */
+
+#include <stdarg.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+#include <libevdev/libevdev.h>
+
+typedef void (*evdev_sys_log_handler)(const struct libevdev *dev,
+				      enum libevdev_log_priority priority,
+				      void *data,
+				      const char *file, int line,
+				      const char *func,
+				      const char *message);
+
+struct evdev_sys_logger {
+	evdev_sys_log_handler handler;
+	void *data;
+};
+
+static void
+forward(struct evdev_sys_logger *logger,
+	const struct libevdev *dev,
+	enum libevdev_log_priority priority,
+	const char *file, int line, const char *func,
+	const char *format, va_list args)
+{
+	char buf[256];
+	char *message = buf;
+	va_list copy;
+	int len;
+
+	va_copy(copy, args);
+	len = vsnprintf(buf, sizeof(buf), format, copy);
+	va_end(copy);
+	if (len < 0)
+		return;
+
+	if ((size_t)len >= sizeof(buf)) {
+		message = malloc(len + 1);
+		if (!message)
+			return;
+		vsnprintf(message, len + 1, format, args);
+	}
+
+	logger->handler(dev, priority, logger->data, file, line, func, message);
+
+	if (message != buf)
+		free(message);
+}
+
+static void
+global_log(enum libevdev_log_priority priority, void *data,
+	   const char *file, int line, const char *func,
+	   const char *format, va_list args)
+{
+	forward(data, NULL, priority, file, line, func, format, args);
+}
+
+static void
+device_log(const struct libevdev *dev,
+	   enum libevdev_log_priority priority, void *data,
+	   const char *file, int line, const char *func,
+	   const char *format, va_list args)
+{
+	forward(data, dev, priority, file, line, func, format, args);
+}
+
+void
+evdev_sys_set_log_function(struct evdev_sys_logger *logger)
+{
+	libevdev_set_log_function(logger ? global_log : NULL, logger);
+}
+
+void
+evdev_sys_set_device_log_function(struct libevdev *dev,
+				  struct evdev_sys_logger *logger,
+				  enum libevdev_log_priority priority)
+{
+	libevdev_set_device_log_function(dev, logger ? device_log : NULL,
+					 priority, logger);
+}
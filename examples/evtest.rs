@@ -1,9 +1,8 @@
 extern crate evdev_rs as evdev;
-extern crate nix;
 
 use evdev::*;
+use evdev::bitset::BitCode;
 use evdev::enums::*;
-use nix::errno::Errno;
 use std::fs::File;
 
 fn usage() {
@@ -32,43 +31,31 @@ fn print_abs_bits(dev: &Device, axis: &EV_ABS) {
     }
 }
 
-fn print_code_bits(dev: &Device, ev_code: &EventCode, max: &EventCode) {
-    for code in ev_code.iter() {
-        if code == *max {
-            break;
-        }
-        if !dev.has(&code) {
-            continue;
-        }
-
-		println!("    Event code: {}", code);
-        match code {
-            EventCode::EV_ABS(k) => print_abs_bits(dev, &k),
-            _ => ()
-        }
+fn print_code_bits<T: BitCode>(codes: &BitSet<T>, code: fn(T) -> EventCode) {
+    for c in codes {
+        println!("    Event code: {}", code(c));
     }
 }
 
 fn print_bits(dev: &Device) {
     println!("Supported events:");
 
-    for ev_type in  EventType::EV_SYN.iter() {
-		if dev.has(&ev_type) {
-			println!("  Event type: {} ", ev_type);
-        }
+    for ev_type in &dev.supported_types() {
+        println!("  Event type: {} ", ev_type);
 
         match ev_type {
-            EventType::EV_KEY => print_code_bits(dev, &EventCode::EV_KEY(EV_KEY::KEY_RESERVED),
-                                                 &EventCode::EV_KEY(EV_KEY::KEY_MAX)),
-            EventType::EV_REL => print_code_bits(dev, &EventCode::EV_REL(EV_REL::REL_X),
-                                                 &EventCode::EV_REL(EV_REL::REL_MAX)),
-            EventType::EV_ABS => print_code_bits(dev, &EventCode::EV_ABS(EV_ABS::ABS_X),
-                                                 &EventCode::EV_ABS(EV_ABS::ABS_MAX)),
-            EventType::EV_LED => print_code_bits(dev, &EventCode::EV_LED(EV_LED::LED_NUML),
-                                                 &EventCode::EV_LED(EV_LED::LED_MAX)),
+            EventType::EV_KEY => print_code_bits(&dev.supported_keys(), EventCode::EV_KEY),
+            EventType::EV_REL => print_code_bits(&dev.supported_rel(), EventCode::EV_REL),
+            EventType::EV_ABS => {
+                for axis in &dev.supported_abs() {
+                    println!("    Event code: {}", EventCode::EV_ABS(axis.clone()));
+                    print_abs_bits(dev, &axis);
+                }
+            },
+            EventType::EV_LED => print_code_bits(&dev.supported_leds(), EventCode::EV_LED),
             _ => (),
-		}
-	}
+        }
+    }
 }
 
 fn print_props(dev: &Device) {
@@ -131,34 +118,26 @@ fn main() {
     print_bits(&d);
     print_props(&d);
 
-    let mut a: Result<(ReadStatus, InputEvent), Errno>;
+    let mut reader = Reader::new(d);
+    let mut syncing = false;
     loop {
-        a = d.next_event(evdev::ReadFlag::NORMAL | evdev::ReadFlag::BLOCKING);
-        if a.is_ok() {
-            let mut result = a.ok().unwrap();
-            match result.0 {
-                ReadStatus::Sync => {
+        match reader.next_event(evdev::ReadFlag::NORMAL | evdev::ReadFlag::BLOCKING) {
+            Ok(ReaderEvent::Event(ev)) => print_event(&ev),
+            Ok(ReaderEvent::Delta(ev)) => {
+                if !syncing {
                     println!("::::::::::::::::::::: dropped ::::::::::::::::::::::");
-                    while result.0 == ReadStatus::Sync {
-                        print_sync_dropped_event(&result.1);
-                        a = d.next_event(evdev::ReadFlag::SYNC);
-                        if a.is_ok() {
-                            result = a.ok().unwrap();
-                        } else {
-                            break;
-                        }
-                    }
-                    println!("::::::::::::::::::::: re-synced ::::::::::::::::::::");
-                },
-                ReadStatus::Success => print_event(&result.1),
-            }
-        } else {
-            match a.err().unwrap() {
-                Errno::EAGAIN => continue,
-                err => {
-                    println!("{}", err);
-                    break;
+                    syncing = true;
                 }
+                print_sync_dropped_event(&ev);
+            },
+            Ok(ReaderEvent::Resynced) => {
+                syncing = false;
+                println!("::::::::::::::::::::: re-synced ::::::::::::::::::::");
+            },
+            Err(Error::WouldBlock) => continue,
+            Err(err) => {
+                println!("{}", err);
+                break;
             }
         }
     }
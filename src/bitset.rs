@@ -0,0 +1,346 @@
+//! Sets of event codes of one type, stored as bitmasks like in the kernel.
+//!
+//! They hold, e.g., the keys held down or the capabilities of a device, see
+//! `Device::key_state` and `Device::supported_keys`.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use evdev_rs::{Device, KeySet};
+//! use evdev_rs::enums::EV_KEY;
+//! use std::fs::File;
+//!
+//! let d = Device::new_from_fd(File::open("/dev/input/event0").unwrap()).unwrap();
+//!
+//! let held = d.key_state().unwrap();
+//! if held.contains(&EV_KEY::KEY_LEFTSHIFT) {
+//!     println!("shift is held");
+//! }
+//! for key in &held {
+//!     println!("{:?}", key);
+//! }
+//!
+//! // The buttons of a mouse, and the ones of the device which are not.
+//! let mouse_buttons: KeySet = vec![EV_KEY::BTN_LEFT, EV_KEY::BTN_RIGHT, EV_KEY::BTN_MIDDLE]
+//!     .into_iter()
+//!     .collect();
+//! let keys = d.supported_keys();
+//! if keys.is_superset(&mouse_buttons) {
+//!     println!("other keys: {:?}", &keys - &mouse_buttons);
+//! }
+//! ```
+
+use libc::c_ulong;
+use std::fmt;
+use std::iter::FromIterator;
+use std::marker::PhantomData;
+use std::ops::{BitAnd, BitOr, BitXor, Sub};
+
+use enums::*;
+
+/// An event code type which can be stored in a `BitSet`.
+pub trait BitCode: Clone + Sized {
+    /// The highest code of the type.
+    const MAX: u32;
+
+    /// Returns the code with the given number, if known.
+    fn from_index(index: u32) -> Option<Self>;
+
+    /// Returns the number of the code.
+    fn index(&self) -> u32;
+}
+
+macro_rules! bit_code {
+    ($($code:ident, $max:expr, $from_int:ident;)*) => {
+        $(
+            impl BitCode for $code {
+                const MAX: u32 = $max as u32;
+
+                fn from_index(index: u32) -> Option<$code> {
+                    $from_int(index)
+                }
+
+                fn index(&self) -> u32 {
+                    self.clone() as u32
+                }
+            }
+        )*
+    };
+}
+
+bit_code! {
+    EV_KEY, EV_KEY::KEY_MAX, int_to_ev_key;
+    EV_REL, EV_REL::REL_MAX, int_to_ev_rel;
+    EV_ABS, EV_ABS::ABS_MAX, int_to_ev_abs;
+    EV_MSC, EV_MSC::MSC_MAX, int_to_ev_msc;
+    EV_SW, EV_SW::SW_MAX, int_to_ev_sw;
+    EV_LED, EV_LED::LED_MAX, int_to_ev_led;
+    EV_SND, EV_SND::SND_MAX, int_to_ev_snd;
+    EV_REP, EV_REP::REP_MAX, int_to_ev_rep;
+    EV_FF, EV_FF::FF_MAX, int_to_ev_ff;
+    InputProp, InputProp::INPUT_PROP_MAX, int_to_input_prop;
+}
+
+impl BitCode for EventType {
+    const MAX: u32 = EventType::EV_MAX as u32;
+
+    fn from_index(index: u32) -> Option<EventType> {
+        int_to_event_type(index).filter(|ev_type| *ev_type != EventType::EV_UNK)
+    }
+
+    fn index(&self) -> u32 {
+        self.clone() as u32
+    }
+}
+
+/// A set of event types, e.g. the types supported by a device.
+pub type EventTypeSet = BitSet<EventType>;
+/// A set of keys, e.g. the keys held down.
+pub type KeySet = BitSet<EV_KEY>;
+/// A set of relative axes.
+pub type RelSet = BitSet<EV_REL>;
+/// A set of absolute axes.
+pub type AbsSet = BitSet<EV_ABS>;
+/// A set of miscellaneous event codes.
+pub type MiscSet = BitSet<EV_MSC>;
+/// A set of switches, e.g. the switches on.
+pub type SwitchSet = BitSet<EV_SW>;
+/// A set of LEDs, e.g. the LEDs lit.
+pub type LedSet = BitSet<EV_LED>;
+/// A set of sounds, e.g. the sounds playing.
+pub type SoundSet = BitSet<EV_SND>;
+/// A set of key repeat settings.
+pub type RepeatSet = BitSet<EV_REP>;
+/// A set of force feedback effect types and settings.
+pub type FfSet = BitSet<EV_FF>;
+/// A set of input properties.
+pub type PropertySet = BitSet<InputProp>;
+
+const WORD_BITS: u32 = u64::BITS;
+
+/// A set of event codes of type `T`.
+///
+/// Codes unknown to evdev-rs, e.g. set by `Device::supported_keys` for a
+/// newer kernel, are kept and compared but not iterated over.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct BitSet<T> {
+    words: Vec<u64>,
+    marker: PhantomData<T>,
+}
+
+impl<T: BitCode> BitSet<T> {
+    /// Create an empty set.
+    pub fn new() -> BitSet<T> {
+        BitSet {
+            words: vec![0; (T::MAX / WORD_BITS + 1) as usize],
+            marker: PhantomData,
+        }
+    }
+
+    /// Build a set from a kernel bitmask, as filled by the `EVIOCG*` ioctls.
+    pub(crate) fn from_raw(bits: &[c_ulong]) -> BitSet<T> {
+        let long_bits = c_ulong::BITS;
+        let mut set = BitSet::new();
+
+        for index in 0..=T::MAX {
+            let word = bits.get((index / long_bits) as usize).cloned().unwrap_or(0);
+            if word & (1 << (index % long_bits)) != 0 {
+                set.insert_index(index);
+            }
+        }
+
+        set
+    }
+
+    /// The number of `c_ulong`s of a kernel bitmask of this code type.
+    pub(crate) fn raw_len() -> usize {
+        let long_bits = c_ulong::BITS;
+        (T::MAX / long_bits + 1) as usize
+    }
+
+    /// Returns `true` if the set contains the code.
+    pub fn contains(&self, code: &T) -> bool {
+        self.contains_index(code.index())
+    }
+
+    /// Add a code to the set. Returns `false` if it was already present.
+    pub fn insert(&mut self, code: &T) -> bool {
+        let present = self.contains(code);
+        self.insert_index(code.index());
+        !present
+    }
+
+    /// Remove a code from the set. Returns `false` if it was not present.
+    pub fn remove(&mut self, code: &T) -> bool {
+        let present = self.contains(code);
+        let index = code.index();
+        if let Some(word) = self.words.get_mut((index / WORD_BITS) as usize) {
+            *word &= !(1 << (index % WORD_BITS));
+        }
+        present
+    }
+
+    /// Remove all the codes.
+    pub fn clear(&mut self) {
+        for word in &mut self.words {
+            *word = 0;
+        }
+    }
+
+    /// Returns the number of codes in the set, including the ones unknown to
+    /// evdev-rs which `iter` skips.
+    pub fn len(&self) -> usize {
+        self.words.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    /// Returns `true` if the set contains no code.
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|&word| word == 0)
+    }
+
+    /// Returns an iterator over the codes of the set, in ascending order.
+    ///
+    /// Codes unknown to evdev-rs are skipped.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            set: self,
+            index: 0,
+        }
+    }
+
+    /// Returns the codes in `self` or in `other`.
+    pub fn union(&self, other: &BitSet<T>) -> BitSet<T> {
+        self.combine(other, |a, b| a | b)
+    }
+
+    /// Returns the codes in both `self` and `other`.
+    pub fn intersection(&self, other: &BitSet<T>) -> BitSet<T> {
+        self.combine(other, |a, b| a & b)
+    }
+
+    /// Returns the codes in `self` but not in `other`.
+    pub fn difference(&self, other: &BitSet<T>) -> BitSet<T> {
+        self.combine(other, |a, b| a & !b)
+    }
+
+    /// Returns the codes in `self` or in `other` but not in both.
+    pub fn symmetric_difference(&self, other: &BitSet<T>) -> BitSet<T> {
+        self.combine(other, |a, b| a ^ b)
+    }
+
+    /// Returns `true` if all the codes of `self` are in `other`.
+    pub fn is_subset(&self, other: &BitSet<T>) -> bool {
+        self.words.iter().zip(&other.words).all(|(a, b)| a & !b == 0)
+    }
+
+    /// Returns `true` if all the codes of `other` are in `self`.
+    pub fn is_superset(&self, other: &BitSet<T>) -> bool {
+        other.is_subset(self)
+    }
+
+    /// Returns `true` if `self` and `other` have no code in common.
+    pub fn is_disjoint(&self, other: &BitSet<T>) -> bool {
+        self.words.iter().zip(&other.words).all(|(a, b)| a & b == 0)
+    }
+
+    fn combine<F: Fn(u64, u64) -> u64>(&self, other: &BitSet<T>, f: F) -> BitSet<T> {
+        BitSet {
+            words: self.words.iter().zip(&other.words).map(|(&a, &b)| f(a, b)).collect(),
+            marker: PhantomData,
+        }
+    }
+
+    fn contains_index(&self, index: u32) -> bool {
+        self.words.get((index / WORD_BITS) as usize)
+            .is_some_and(|word| word & (1 << (index % WORD_BITS)) != 0)
+    }
+
+    pub(crate) fn insert_index(&mut self, index: u32) {
+        if let Some(word) = self.words.get_mut((index / WORD_BITS) as usize) {
+            *word |= 1 << (index % WORD_BITS);
+        }
+    }
+}
+
+impl<T: BitCode> Default for BitSet<T> {
+    fn default() -> BitSet<T> {
+        BitSet::new()
+    }
+}
+
+impl<T: BitCode + fmt::Debug> fmt::Debug for BitSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl<T: BitCode> FromIterator<T> for BitSet<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> BitSet<T> {
+        let mut set = BitSet::new();
+        for code in iter {
+            set.insert(&code);
+        }
+        set
+    }
+}
+
+impl<T: BitCode> Extend<T> for BitSet<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for code in iter {
+            self.insert(&code);
+        }
+    }
+}
+
+macro_rules! set_operator {
+    ($($op:ident, $method:ident, $set_method:ident;)*) => {
+        $(
+            impl<'a, 'b, T: BitCode> $op<&'b BitSet<T>> for &'a BitSet<T> {
+                type Output = BitSet<T>;
+
+                fn $method(self, other: &'b BitSet<T>) -> BitSet<T> {
+                    self.$set_method(other)
+                }
+            }
+        )*
+    };
+}
+
+set_operator! {
+    BitOr, bitor, union;
+    BitAnd, bitand, intersection;
+    Sub, sub, difference;
+    BitXor, bitxor, symmetric_difference;
+}
+
+impl<'a, T: BitCode> IntoIterator for &'a BitSet<T> {
+    type Item = T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+/// An iterator over the codes of a `BitSet`.
+pub struct Iter<'a, T: 'a> {
+    set: &'a BitSet<T>,
+    index: u32,
+}
+
+impl<'a, T: BitCode> Iterator for Iter<'a, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        while self.index <= T::MAX {
+            let index = self.index;
+            self.index += 1;
+            if self.set.contains_index(index) {
+                if let Some(code) = T::from_index(index) {
+                    return Some(code);
+                }
+            }
+        }
+        None
+    }
+}
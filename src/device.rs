@@ -1,20 +1,108 @@
 use {TimeVal, ReadStatus, InputEvent, LedState, ReadFlag, GrabMode, AbsInfo};
-use libc::{c_int, c_uint, c_void};
+use libc::{self, c_int, c_uint, c_ulong, c_void};
+use error::Error;
 use nix::errno::Errno;
-use std::any::Any;
+use std::cell::RefCell;
 use std::ffi::CString;
 use std::fs::File;
-use std::os::unix::io::{AsRawFd, FromRawFd};
+use std::mem;
+use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
 use std::ptr;
 
 use enums::*;
+use bitset::{AbsSet, BitCode, BitSet, EventTypeSet, FfSet, KeySet, LedSet, MiscSet, PropertySet,
+             RelSet, SoundSet, SwitchSet};
+use ff::Effect;
+use frame::{Frame, Frames};
+use ioctl;
+use keymap::KeymapEntry;
+use logging::{DeviceLogger, LogPriority, LogRecord};
 use util::*;
 
+/// What a device may support: an `EventType`, an `EventCode` or an
+/// `InputProp`, as taken by `Device::has`, `enable` and `disable`.
+///
+/// This trait is sealed, it cannot be implemented outside of evdev-rs.
+pub trait Capability: private::Sealed {}
+
+impl Capability for EventType {}
+impl Capability for EventCode {}
+impl Capability for InputProp {}
+
+mod private {
+    use super::Device;
+    use error::Error;
+
+    pub trait Sealed {
+        fn has(&self, device: &Device) -> bool;
+        fn enable(&self, device: &Device) -> Result<(), Error>;
+        fn disable(&self, device: &Device) -> Result<(), Error>;
+    }
+}
+
+impl private::Sealed for EventType {
+    fn has(&self, device: &Device) -> bool {
+        device.has_event_type(self)
+    }
+
+    fn enable(&self, device: &Device) -> Result<(), Error> {
+        device.enable_event_type(self)
+    }
+
+    fn disable(&self, device: &Device) -> Result<(), Error> {
+        device.disable_event_type(self)
+    }
+}
+
+impl private::Sealed for EventCode {
+    fn has(&self, device: &Device) -> bool {
+        device.has_event_code(self)
+    }
+
+    fn enable(&self, device: &Device) -> Result<(), Error> {
+        device.enable_event_code(self, EnableData::None)
+    }
+
+    fn disable(&self, device: &Device) -> Result<(), Error> {
+        device.disable_event_code(self)
+    }
+}
+
+impl private::Sealed for InputProp {
+    fn has(&self, device: &Device) -> bool {
+        device.has_property(self)
+    }
+
+    fn enable(&self, device: &Device) -> Result<(), Error> {
+        device.enable_property(self)
+    }
+
+    fn disable(&self, _: &Device) -> Result<(), Error> {
+        // libevdev has no way to disable a property.
+        Err(Error::UnsupportedCapability(self.to_string()))
+    }
+}
+
+/// The data an event code is enabled with by `Device::enable_event_code`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EnableData {
+    /// The axis of an `EV_ABS` code.
+    Abs(AbsInfo),
+    /// The value of an `EV_REP` code, in milliseconds.
+    Rep(i32),
+    /// No data, for the codes of the other types.
+    None,
+}
+
 /// Opaque struct representing an evdev device
 pub struct Device {
     // The file descriptor of the device must live as long as the device itself.
     _file: Option<File>,
     pub(crate) raw: *mut raw::libevdev,
+    /// The events read by `next_frame` of the frame not yet complete.
+    frame: RefCell<Vec<InputEvent>>,
+    /// The handler installed by `set_log_handler`, referenced by libevdev.
+    log_handler: Option<Box<DeviceLogger>>,
 }
 
 impl Device {
@@ -33,6 +121,8 @@ impl Device {
             Some(Device {
                 _file: None,
                 raw: libevdev,
+                frame: RefCell::new(Vec::new()),
+                log_handler: None,
             })
         }
     }
@@ -49,15 +139,21 @@ impl Device {
     /// # let fd = File::open("/dev/input/event0").unwrap();
     /// device.set_fd(fd);
     /// ```
-    pub fn new_from_fd(file: File) -> Result<Device, Errno> {
+    pub fn new_from_fd(file: File) -> Result<Device, Error> {
         let mut libevdev = 0 as *mut _;
         let result = unsafe {
             raw::libevdev_new_from_fd(file.as_raw_fd(), &mut libevdev)
         };
 
         match result {
-            0 => Ok(Device { _file: Some(file), raw: libevdev }),
-            error => Err(Errno::from_i32(-error)),
+            0 => Ok(Device {
+                _file: Some(file),
+                raw: libevdev,
+                frame: RefCell::new(Vec::new()),
+                log_handler: None,
+            }),
+            error => Err(Error::libevdev("libevdev_new_from_fd", error)
+                         .with_fd_path(Some(file.as_raw_fd()))),
         }
     }
 
@@ -94,7 +190,7 @@ impl Device {
     ///
     /// Unless otherwise specified, evdev function behavior is undefined until
     /// a successfull call to `set_fd`.
-    pub fn set_fd(&mut self, file: File) -> Result<(), Errno> {
+    pub fn set_fd(&mut self, file: File) -> Result<(), Error> {
         let result = unsafe {
             raw::libevdev_set_fd(self.raw, file.as_raw_fd())
         };
@@ -104,7 +200,8 @@ impl Device {
                 self._file = Some(file);
                 Ok(())
             },
-            error => Err(Errno::from_i32(-error))
+            error => Err(Error::libevdev("libevdev_set_fd", error)
+                         .with_fd_path(Some(file.as_raw_fd())))
         }
     }
 
@@ -130,7 +227,7 @@ impl Device {
     /// call libevdev_grab() again.
     ///
     /// It is an error to call this function before calling set_fd().
-    pub fn change_fd(&mut self, file: File) -> Result<(), Errno>  {
+    pub fn change_fd(&mut self, file: File) -> Result<(), Error>  {
         let result = unsafe {
             raw::libevdev_change_fd(self.raw, file.as_raw_fd())
         };
@@ -138,9 +235,11 @@ impl Device {
         match result {
             0 => {
                 self._file = Some(file);
+                self.frame.borrow_mut().clear();
                 Ok(())
             },
-            error => Err(Errno::from_i32(-error))
+            error => Err(Error::libevdev("libevdev_change_fd", error)
+                         .with_fd_path(Some(file.as_raw_fd())))
         }
     }
 
@@ -154,17 +253,52 @@ impl Device {
     /// A grab is an operation tied to a file descriptor, not a device. If a
     /// client changes the file descriptor with libevdev_change_fd(), it must
     /// also re-issue a grab with libevdev_grab().
-    pub fn grab(&mut self, grab: GrabMode) -> Result<(), Errno> {
+    pub fn grab(&mut self, grab: GrabMode) -> Result<(), Error> {
         let result = unsafe {
             raw::libevdev_grab(self.raw, grab as c_int)
         };
 
         match result {
             0 => Ok(()),
-            error => Err(Errno::from_i32(-error)),
+            error => Err(self.error(Error::libevdev("libevdev_grab", error))),
         }
     }
 
+    /// Handle the log messages of libevdev about this device with `handler`
+    /// instead of the global handler. Only messages of `priority` or more
+    /// severe are passed to it.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use evdev_rs::Device;
+    /// use evdev_rs::logging::{self, LogPriority};
+    /// use std::fs::File;
+    ///
+    /// let mut d = Device::new_from_fd(File::open("/dev/input/event0").unwrap()).unwrap();
+    /// d.set_log_handler(LogPriority::Info, logging::forward_to_log);
+    /// ```
+    pub fn set_log_handler<F>(&mut self, priority: LogPriority, handler: F)
+        where F: Fn(&LogRecord) + 'static
+    {
+        let mut logger = DeviceLogger::new(Box::new(handler));
+        unsafe {
+            raw::evdev_sys_set_device_log_function(self.raw, logger.as_raw(),
+                                                   priority as raw::libevdev_log_priority);
+        }
+        self.log_handler = Some(logger);
+    }
+
+    /// Remove the handler installed by `set_log_handler`, handing the log
+    /// messages about this device back to the global handler.
+    pub fn clear_log_handler(&mut self) {
+        unsafe {
+            raw::evdev_sys_set_device_log_function(self.raw, ptr::null_mut(),
+                                                   raw::LIBEVDEV_LOG_DEBUG);
+        }
+        self.log_handler = None;
+    }
+
     /// Get the axis info for the given axis, as advertised by the kernel.
     ///
     /// Returns the `AbsInfo` for the given the code or None if the device
@@ -206,34 +340,21 @@ impl Device {
     }
 
     /// Returns `true` if device support the InputProp/EventType/EventCode and false otherwise
-    pub fn has(&self, blob: &dyn Any) -> bool {
-        if let Some(ev_type) = blob.downcast_ref::<EventType>() {
-            self.has_event_type(ev_type)
-        } else if let Some(ev_code) = blob.downcast_ref::<EventCode>() {
-            self.has_event_code(ev_code)
-        } else if let Some(prop) = blob.downcast_ref::<InputProp>() {
-            self.has_property(prop)
-        } else {
-            false
-        }
+    pub fn has<C: Capability + ?Sized>(&self, capability: &C) -> bool {
+        capability.has(self)
     }
 
-    /// Forcibly enable an EventType/InputProp on this device, even if the underlying
-    /// device does not support it. While this cannot make the device actually
-    /// report such events, it will now return true for has().
+    /// Forcibly enable an EventType/EventCode/InputProp on this device, even if
+    /// the underlying device does not support it. While this cannot make the
+    /// device actually report such events, it will now return true for has().
+    ///
+    /// Event codes are enabled without data, use `enable_event_code` for the
+    /// ones of EV_ABS and EV_REP.
     ///
     /// This is a local modification only affecting only this representation of
     /// this device.
-    pub fn enable(&self, blob: &dyn Any) -> Result<(),Errno> {
-        if let Some(ev_type) = blob.downcast_ref::<EventType>() {
-            self.enable_event_type(ev_type)
-        } else if let Some(ev_code) = blob.downcast_ref::<EventCode>() {
-            self.enable_event_code(ev_code, None)
-        } else if let Some(prop) = blob.downcast_ref::<InputProp>() {
-            self.enable_property(prop)
-        } else {
-            Err(Errno::from_i32(-1))
-        }
+    pub fn enable<C: Capability + ?Sized>(&self, capability: &C) -> Result<(), Error> {
+        capability.enable(self)
     }
 
     /// Returns `true` if device support the property and false otherwise
@@ -250,14 +371,14 @@ impl Device {
     ///
     /// Note: Please use the `enable` function instead. This function is only
     /// available for the sake of maintaining compatibility with libevdev.
-    pub fn enable_property(&self, prop: &InputProp) -> Result<(), Errno> {
+    pub fn enable_property(&self, prop: &InputProp) -> Result<(), Error> {
         let result = unsafe {
             raw::libevdev_enable_property(self.raw, prop.clone() as c_uint) as i32
         };
 
         match result {
             0 => Ok(()),
-            error => Err(Errno::from_i32(-error))
+            _ => Err(Error::InvalidCode(prop.to_string()))
         }
     }
     /// Returns `true` is the device support this event type and `false` otherwise
@@ -283,6 +404,77 @@ impl Device {
         }
     }
 
+    /// Returns the event types supported by the device.
+    pub fn supported_types(&self) -> EventTypeSet {
+        self.supported(|ev_type| unsafe {
+            raw::libevdev_has_event_type(self.raw, ev_type) != 0
+        })
+    }
+
+    /// Returns the keys and buttons supported by the device.
+    pub fn supported_keys(&self) -> KeySet {
+        self.supported_codes(EventType::EV_KEY)
+    }
+
+    /// Returns the relative axes supported by the device.
+    pub fn supported_rel(&self) -> RelSet {
+        self.supported_codes(EventType::EV_REL)
+    }
+
+    /// Returns the absolute axes supported by the device.
+    pub fn supported_abs(&self) -> AbsSet {
+        self.supported_codes(EventType::EV_ABS)
+    }
+
+    /// Returns the miscellaneous event codes supported by the device.
+    pub fn supported_misc(&self) -> MiscSet {
+        self.supported_codes(EventType::EV_MSC)
+    }
+
+    /// Returns the switches supported by the device.
+    pub fn supported_switches(&self) -> SwitchSet {
+        self.supported_codes(EventType::EV_SW)
+    }
+
+    /// Returns the LEDs supported by the device.
+    pub fn supported_leds(&self) -> LedSet {
+        self.supported_codes(EventType::EV_LED)
+    }
+
+    /// Returns the sounds supported by the device.
+    pub fn supported_sounds(&self) -> SoundSet {
+        self.supported_codes(EventType::EV_SND)
+    }
+
+    /// Returns the force feedback effect types and settings supported by the
+    /// device.
+    pub fn supported_ff(&self) -> FfSet {
+        self.supported_codes(EventType::EV_FF)
+    }
+
+    /// Returns the properties of the device.
+    pub fn supported_properties(&self) -> PropertySet {
+        self.supported(|prop| unsafe {
+            raw::libevdev_has_property(self.raw, prop) != 0
+        })
+    }
+
+    fn supported_codes<T: BitCode>(&self, ev_type: EventType) -> BitSet<T> {
+        self.supported(|code| unsafe {
+            raw::libevdev_has_event_code(self.raw, ev_type.clone() as c_uint, code) != 0
+        })
+    }
+
+    /// Returns the set of the codes for which `has` returns `true`, including
+    /// the ones unknown to evdev-rs.
+    fn supported<T: BitCode, F: Fn(c_uint) -> bool>(&self, has: F) -> BitSet<T> {
+        let mut set = BitSet::new();
+        for index in (0..=T::MAX).filter(|&index| has(index)) {
+            set.insert_index(index);
+        }
+        set
+    }
+
     ///  Returns the current value of the event type.
     ///
     /// If the device supports this event type and code, the return value is
@@ -320,7 +512,7 @@ impl Device {
     /// ABS_MT_SLOT, the value must be a positive number less then the number of
     /// slots on the device. Otherwise, `set_event_value` returns Err.
     pub fn set_event_value(&self, code: &EventCode, val: i32)
-                           -> Result<(), Errno> {
+                           -> Result<(), Error> {
             let (ev_type, ev_code) = event_code_to_int(code);
             let result = unsafe {
                 raw::libevdev_set_event_value(self.raw,
@@ -331,7 +523,7 @@ impl Device {
 
             match result {
                0 => Ok(()),
-               error => Err(Errno::from_i32(-error))
+               _ => Err(Error::UnsupportedCapability(code.to_string()))
             }
     }
 
@@ -413,7 +605,7 @@ impl Device {
     /// This function does not set event values for axes outside the ABS_MT range,
     /// use `set_event_value` instead.
     pub fn set_slot_value(&self, slot: u32, code: &EventCode, val: i32)
-                          -> Result<(), Errno> {
+                          -> Result<(), Error> {
         let (_, ev_code) = event_code_to_int(code);
         let result = unsafe {
             raw::libevdev_set_slot_value(self.raw,
@@ -424,7 +616,7 @@ impl Device {
 
         match result {
             0 => Ok(()),
-            error => Err(Errno::from_i32(-error))
+            _ => Err(Error::UnsupportedCapability(format!("{} in slot {}", code, slot)))
         }
     }
 
@@ -473,7 +665,7 @@ impl Device {
     ///
     /// Note: Please use the `enable` function instead. This function is only
     /// available for the sake of maintaining compatibility with libevdev.
-    pub fn enable_event_type(&self, ev_type: &EventType) -> Result<(), Errno> {
+    pub fn enable_event_type(&self, ev_type: &EventType) -> Result<(), Error> {
          let result = unsafe {
             raw::libevdev_enable_event_type(self.raw,
                                             ev_type.clone() as c_uint)
@@ -481,7 +673,7 @@ impl Device {
 
         match result {
             0 => Ok(()),
-            error => Err(Errno::from_i32(-error))
+            _ => Err(Error::InvalidCode(ev_type.to_string()))
         }
     }
 
@@ -489,26 +681,31 @@ impl Device {
     /// device does not support it. While this cannot make the device actually
     /// report such events, it will now return true for libevdev_has_event_code().
     ///
-    /// The last argument depends on the type and code:
-    /// If type is EV_ABS, data must be a pointer to a struct input_absinfo
-    /// containing the data for this axis.
-    /// If type is EV_REP, data must be a pointer to a int containing the data
-    /// for this axis.
-    /// For all other types, the argument must be NULL.
-    ///
-    /// Note: Please use the `enable` function instead. This function is only
-    /// available for the sake of maintaining compatibility with libevdev.
-    pub fn enable_event_code(&self, ev_code: &EventCode, blob: Option<&dyn Any>)
-                             -> Result<(), Errno> {
-        let (ev_type, ev_code) = event_code_to_int(ev_code);
-
-        let data = blob
-            .map(|data| {
-                data.downcast_ref::<AbsInfo>()
-                    .map(|absinfo| &absinfo.as_raw() as *const _ as *const c_void)
-                    .unwrap_or_else(|| data as *const _ as *const c_void)
-            })
-            .unwrap_or_else(|| ptr::null() as *const _ as *const c_void);
+    /// The data depends on the type of the code:
+    /// If type is EV_ABS, data must be `EnableData::Abs` with the axis.
+    /// If type is EV_REP, data must be `EnableData::Rep` with the value.
+    /// For all other types, data must be `EnableData::None`.
+    /// Any other data fails with `Error::InvalidCode`.
+    pub fn enable_event_code(&self, code: &EventCode, data: EnableData)
+                             -> Result<(), Error> {
+        let (ev_type, ev_code) = event_code_to_int(code);
+
+        // The data pointed to must outlive the call.
+        let absinfo;
+        let rep: c_int;
+        let data = match (ev_type, data) {
+            (t, EnableData::Abs(info)) if t == EventType::EV_ABS as c_uint => {
+                absinfo = info.as_raw();
+                &absinfo as *const _ as *const c_void
+            },
+            (t, EnableData::Rep(value)) if t == EventType::EV_REP as c_uint => {
+                rep = value;
+                &rep as *const c_int as *const c_void
+            },
+            (t, EnableData::None) if t != EventType::EV_ABS as c_uint &&
+                                      t != EventType::EV_REP as c_uint => ptr::null(),
+            _ => return Err(Error::InvalidCode(code.to_string())),
+        };
 
         let result = unsafe {
             raw::libevdev_enable_event_code(self.raw,
@@ -519,7 +716,7 @@ impl Device {
 
         match result {
             0 => Ok(()),
-            error => Err(Errno::from_i32(-error))
+            _ => Err(Error::InvalidCode(code.to_string()))
         }
     }
 
@@ -535,14 +732,11 @@ impl Device {
     ///
     /// This is a local modification only affecting only this representation of
     /// this device.
-    pub fn disable(&self, blob: &dyn Any) -> Result<(),Errno> {
-        if let Some(ev_type) = blob.downcast_ref::<EventType>() {
-            self.disable_event_type(ev_type)
-        } else if let Some(ev_code) = blob.downcast_ref::<EventCode>() {
-            self.disable_event_code(ev_code)
-        } else {
-            Err(Errno::from_i32(-1))
-        }
+    ///
+    /// Properties cannot be disabled, this fails with
+    /// `Error::UnsupportedCapability` for an InputProp.
+    pub fn disable<C: Capability + ?Sized>(&self, capability: &C) -> Result<(), Error> {
+        capability.disable(self)
     }
 
     /// Forcibly disable an event type on this device, even if the underlying
@@ -562,7 +756,7 @@ impl Device {
     ///
     /// Note: Please use the `disable` function instead. This function is only
     /// available for the sake of maintaining compatibility with libevdev.
-    pub fn disable_event_type(&self, ev_type: &EventType) -> Result<(), Errno> {
+    pub fn disable_event_type(&self, ev_type: &EventType) -> Result<(), Error> {
          let result = unsafe {
             raw::libevdev_disable_event_type(self.raw,
                                              ev_type.clone() as c_uint)
@@ -570,7 +764,7 @@ impl Device {
 
         match result {
             0 => Ok(()),
-            error => Err(Errno::from_i32(-error))
+            _ => Err(Error::InvalidCode(ev_type.to_string()))
         }
     }
     /// Forcibly disable an event code on this device, even if the underlying
@@ -591,7 +785,7 @@ impl Device {
     /// Note: Please use the `disable` function instead. This function is only
     /// available for the sake of maintaining compatibility with libevdev.
     pub fn disable_event_code(&self, code: &EventCode)
-                              -> Result<(), Errno> {
+                              -> Result<(), Error> {
         let (ev_type, ev_code) = event_code_to_int(code);
         let result = unsafe {
             raw::libevdev_disable_event_code(self.raw,
@@ -601,7 +795,7 @@ impl Device {
 
         match result {
             0 => Ok(()),
-            error => Err(Errno::from_i32(-error))
+            _ => Err(Error::InvalidCode(code.to_string()))
         }
     }
 
@@ -618,9 +812,9 @@ impl Device {
 
     /// Turn an LED on or off.
     ///
-    /// enabling an LED requires write permissions on the device's file descriptor.
+    /// Like `kernel_set_led_values`, this requires write permissions.
     pub fn kernel_set_led_value(&self, code: &EventCode, value: LedState)
-                                 -> Result<(), Errno> {
+                                 -> Result<(), Error> {
         let (_, ev_code) = event_code_to_int(code);
         let result = unsafe {
             raw::libevdev_kernel_set_led_value(self.raw,
@@ -630,16 +824,56 @@ impl Device {
 
         match result {
             0 => Ok(()),
-            error => Err(Errno::from_i32(-error))
+            error => Err(self.error(Error::libevdev("libevdev_kernel_set_led_value", error)))
         }
     }
 
+    /// Turn several LEDs on or off at once.
+    ///
+    /// The LED events are written followed by a single `SYN_REPORT`, so the
+    /// kernel applies them together. Fails with `UnsupportedCapability`,
+    /// without changing any LED, if the device does not have one of them.
+    ///
+    /// Setting LEDs requires the device's file descriptor to be opened for
+    /// writing.
+    pub fn kernel_set_led_values(&self, leds: &[(EV_LED, LedState)]) -> Result<(), Error> {
+        let file = self._file.as_ref().ok_or(Error::os("write", Errno::EBADF))?;
+        let unsupported = leds.iter()
+            .map(|(led, _)| EventCode::EV_LED(led.clone()))
+            .find(|code| !self.has_event_code(code));
+        if let Some(code) = unsupported {
+            return Err(Error::UnsupportedCapability(code.to_string()));
+        }
+
+        let values: Vec<(EventCode, i32)> = leds.iter().map(|(led, state)| {
+            let value = match *state {
+                LedState::On => 1,
+                LedState::Off => 0,
+            };
+            (EventCode::EV_LED(led.clone()), value)
+        }).collect();
+
+        let time = TimeVal::new(0, 0);
+        let mut events: Vec<_> = values.iter()
+            .map(|&(ref code, value)| InputEvent::new(&time, code, value).as_raw())
+            .collect();
+        events.push(InputEvent::new(&time, &EventCode::EV_SYN(EV_SYN::SYN_REPORT), 0).as_raw());
+        write_structs(file, &events).map_err(|e| self.error(e))?;
+
+        // Like libevdev, update the cached state right away.
+        for (code, value) in values {
+            self.set_event_value(&code, value)?;
+        }
+
+        Ok(())
+    }
+
     /// Set the clock ID to be used for timestamps. Further events from this device
     /// will report an event time based on the given clock.
     ///
     /// This is a modification only affecting this representation of
     /// this device.
-    pub fn set_clock_id(&self, clockid: i32) -> Result<(), Errno> {
+    pub fn set_clock_id(&self, clockid: i32) -> Result<(), Error> {
          let result = unsafe {
             raw::libevdev_set_clock_id(self.raw,
                                        clockid as c_int)
@@ -647,7 +881,7 @@ impl Device {
 
         match result {
             0 => Ok(()),
-            error => Err(Errno::from_i32(-error))
+            error => Err(self.error(Error::libevdev("libevdev_set_clock_id", error)))
         }
     }
 
@@ -656,14 +890,14 @@ impl Device {
     ///
     /// In normal mode (when flags has `evdev::NORMAL` set), this function returns
     /// `ReadStatus::Success` and returns the event. If no events are available at
-    /// this time, it returns `-EAGAIN` as `Err`.
+    /// this time, it returns `Error::WouldBlock`.
     ///
     /// If the current event is an `EV_SYN::SYN_DROPPED` event, this function returns
     /// `ReadStatus::Sync` and is set to the `EV_SYN` event.The caller should now call
     /// this function with the `evdev::SYNC` flag set, to get the set of events that
     /// make up the device state delta. This function returns ReadStatus::Sync for
-    /// each event part of that delta, until it returns `-EAGAIN` once all events
-    /// have been synced.
+    /// each event part of that delta, until it returns `Error::WouldBlock` once all
+    /// events have been synced.
     ///
     /// If a device needs to be synced by the caller but the caller does not call
     /// with the `evdev::SYNC` flag set, all events from the diff are dropped after
@@ -678,7 +912,7 @@ impl Device {
     /// This triggers an internal sync of the device and `next_event` returns
     /// `ReadStatus::Sync`.
     pub fn next_event(&self, flags: ReadFlag)
-                      -> Result<(ReadStatus, InputEvent), Errno> {
+                      -> Result<(ReadStatus, InputEvent), Error> {
         let mut ev = raw::input_event {
             time: raw::timeval {
                 tv_sec: 0,
@@ -706,7 +940,298 @@ impl Device {
         match result {
             raw::LIBEVDEV_READ_STATUS_SUCCESS => Ok((ReadStatus::Success, event)),
             raw::LIBEVDEV_READ_STATUS_SYNC => Ok((ReadStatus::Sync, event)),
-            error => Err(Errno::from_i32(-error)),
+            error => Err(self.error(Error::libevdev("libevdev_next_event", error))),
+        }
+    }
+
+    /// Read the events up to the next `SYN_REPORT`.
+    ///
+    /// `flags` are passed to `next_event` and must not include
+    /// `ReadFlag::SYNC`. If the fd is non-blocking and the frame is not
+    /// complete yet, `Error::WouldBlock` is returned and the events read so far
+    /// are kept for the next call.
+    ///
+    /// A frame cut short by a `SYN_DROPPED` is discarded. The device state
+    /// delta computed by libevdev is then returned as a single frame marked
+    /// `synthetic`.
+    pub fn next_frame(&self, flags: ReadFlag) -> Result<Frame, Error> {
+        loop {
+            let (status, event) = self.next_event(flags)?;
+            if status == ReadStatus::Sync {
+                self.frame.borrow_mut().clear();
+                return self.sync_frame();
+            }
+
+            if event.event_code == EventCode::EV_SYN(EV_SYN::SYN_REPORT) {
+                return Ok(Frame {
+                    time: event.time,
+                    events: self.frame.borrow_mut().drain(..).collect(),
+                    synthetic: false,
+                });
+            }
+            self.frame.borrow_mut().push(event);
+        }
+    }
+
+    /// Returns an iterator calling `next_frame` with
+    /// `ReadFlag::NORMAL | ReadFlag::BLOCKING`.
+    pub fn frames(&self) -> Frames<'_> {
+        Frames {
+            device: self,
+            flags: ReadFlag::NORMAL | ReadFlag::BLOCKING,
+        }
+    }
+
+    /// Read the whole sync delta as one frame, merging the frames libevdev
+    /// splits it into.
+    fn sync_frame(&self) -> Result<Frame, Error> {
+        let mut frame = Frame {
+            time: TimeVal::new(0, 0),
+            events: Vec::new(),
+            synthetic: true,
+        };
+
+        loop {
+            match self.next_event(ReadFlag::SYNC) {
+                Ok((_, event)) => {
+                    if event.event_code == EventCode::EV_SYN(EV_SYN::SYN_REPORT) {
+                        frame.time = event.time;
+                    } else {
+                        frame.events.push(event);
+                    }
+                },
+                Err(Error::WouldBlock) => return Ok(frame),
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
+    /// Returns the kernel's key repeat delay and period, in milliseconds,
+    /// through a kernel EVIOCGREP.
+    ///
+    /// Fails with `ENOSYS` if the device does not support `EV_REP`.
+    pub fn repeat_settings(&self) -> Result<(i32, i32), Error> {
+        let mut rep: [c_uint; 2] = [0; 2];
+        unsafe {
+            ioctl::eviocgrep(self.as_raw_fd(), &mut rep)
+                .map_err(|e| self.error(Error::nix("EVIOCGREP", e)))?;
+        }
+        Ok((rep[0] as i32, rep[1] as i32))
+    }
+
+    /// Change the kernel's key repeat delay and period, in milliseconds,
+    /// through a kernel EVIOCSREP.
+    ///
+    /// This changes the repeat for all the clients of the device. The
+    /// `EV_REP` values of this device are updated as well. Negative values
+    /// are rejected with `Error::InvalidCode`.
+    pub fn set_kernel_repeat(&self, delay: i32, period: i32) -> Result<(), Error> {
+        if delay < 0 {
+            return Err(Error::InvalidCode(format!("REP_DELAY of {} ms", delay)));
+        }
+        if period < 0 {
+            return Err(Error::InvalidCode(format!("REP_PERIOD of {} ms", period)));
+        }
+
+        let rep: [c_uint; 2] = [delay as c_uint, period as c_uint];
+        unsafe {
+            ioctl::eviocsrep(self.as_raw_fd(), &rep)
+                .map_err(|e| self.error(Error::nix("EVIOCSREP", e)))?;
+        }
+
+        self.enable_event_code(&EventCode::EV_REP(EV_REP::REP_DELAY), EnableData::Rep(delay))?;
+        self.enable_event_code(&EventCode::EV_REP(EV_REP::REP_PERIOD), EnableData::Rep(period))
+    }
+
+    /// Returns the keys held down, through a kernel EVIOCGKEY.
+    ///
+    /// Unlike `event_value`, this bypasses the state cached by libevdev.
+    pub fn key_state(&self) -> Result<KeySet, Error> {
+        self.kernel_state("EVIOCGKEY", ioctl::eviocgkey)
+    }
+
+    /// Returns the LEDs lit, through a kernel EVIOCGLED.
+    pub fn led_state(&self) -> Result<LedSet, Error> {
+        self.kernel_state("EVIOCGLED", ioctl::eviocgled)
+    }
+
+    /// Returns the switches on, through a kernel EVIOCGSW.
+    pub fn switch_state(&self) -> Result<SwitchSet, Error> {
+        self.kernel_state("EVIOCGSW", ioctl::eviocgsw)
+    }
+
+    /// Returns the sounds playing, through a kernel EVIOCGSND.
+    pub fn sound_state(&self) -> Result<SoundSet, Error> {
+        self.kernel_state("EVIOCGSND", ioctl::eviocgsnd)
+    }
+
+    fn kernel_state<T: BitCode>(&self, operation: &'static str,
+                                ioctl: unsafe fn(c_int, &mut [c_ulong]) -> nix::Result<c_int>)
+                                -> Result<BitSet<T>, Error> {
+        let mut bits = vec![0; BitSet::<T>::raw_len()];
+        unsafe {
+            ioctl(self.as_raw_fd(), &mut bits).map_err(|e| self.error(Error::nix(operation, e)))?;
+        }
+        Ok(BitSet::from_raw(&bits))
+    }
+
+    /// Returns all the entries of the keymap, through kernel
+    /// EVIOCGKEYCODE_V2 calls by index.
+    pub fn keymap_entries(&self) -> Result<Vec<KeymapEntry>, Error> {
+        let mut entries = Vec::new();
+
+        for index in 0..=u16::MAX {
+            let mut entry: libc::input_keymap_entry = unsafe { mem::zeroed() };
+            entry.flags = ioctl::INPUT_KEYMAP_BY_INDEX;
+            entry.index = index;
+
+            match self.keymap_entry(&mut entry) {
+                Ok(()) => entries.push(keymap_entry_from_raw(&entry)),
+                // The index is past the end of the keymap.
+                Err(ref error) if error.errno() == Some(Errno::EINVAL) => break,
+                Err(error) => return Err(error),
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// Returns the key reported for a scancode, through a kernel
+    /// EVIOCGKEYCODE_V2.
+    pub fn get_keycode(&self, scancode: &[u8]) -> Result<EventCode, Error> {
+        let mut entry = keymap_entry_for(scancode)?;
+        self.keymap_entry(&mut entry)?;
+        Ok(keymap_entry_from_raw(&entry).keycode)
+    }
+
+    /// Change the key reported for a scancode through a kernel
+    /// EVIOCSKEYCODE_V2.
+    ///
+    /// This changes the keymap of the device itself, for all the clients.
+    pub fn set_keycode(&self, scancode: &[u8], key: &EV_KEY) -> Result<(), Error> {
+        let mut entry = keymap_entry_for(scancode)?;
+        entry.keycode = key.clone() as u32;
+
+        unsafe {
+            ioctl::eviocskeycode_v2(self.as_raw_fd(), &entry)
+        }.map(|_| ()).map_err(|e| self.error(Error::nix("EVIOCSKEYCODE_V2", e)))
+    }
+
+    fn keymap_entry(&self, entry: &mut libc::input_keymap_entry) -> Result<(), Error> {
+        unsafe {
+            ioctl::eviocgkeycode_v2(self.as_raw_fd(), entry)
+        }.map(|_| ()).map_err(|e| self.error(Error::nix("EVIOCGKEYCODE_V2", e)))
+    }
+
+    /// Upload a force feedback effect to the device through a kernel EVIOCSFF.
+    ///
+    /// If the id of the effect is -1 a new effect is created, otherwise the
+    /// effect with this id is updated. Returns the id of the effect, to be
+    /// passed to `play_effect` and `erase_effect`.
+    ///
+    /// Uploading effects requires write permissions on the device's file
+    /// descriptor.
+    pub fn upload_effect(&self, effect: &Effect) -> Result<i16, Error> {
+        let mut raw_effect = effect.as_raw();
+        let ptr: *mut libc::ff_effect = &mut raw_effect;
+        unsafe {
+            ioctl::eviocsff(self.as_raw_fd(), ptr)
+                .map_err(|e| self.error(Error::nix("EVIOCSFF", e)))?;
+        }
+
+        Ok(raw_effect.id)
+    }
+
+    /// Remove an uploaded effect from the device through a kernel EVIOCRMFF.
+    pub fn erase_effect(&self, id: i16) -> Result<(), Error> {
+        unsafe {
+            ioctl::eviocrmff(self.as_raw_fd(), id as libc::c_ulong)
+                .map_err(|e| self.error(Error::nix("EVIOCRMFF", e)))?;
+        }
+
+        Ok(())
+    }
+
+    /// Start playing an uploaded effect `count` times.
+    ///
+    /// This writes an `EV_FF` event to the device, which requires write
+    /// permissions on the device's file descriptor.
+    pub fn play_effect(&self, id: i16, count: i32) -> Result<(), Error> {
+        self.write_ff_event(id as u16, count)
+    }
+
+    /// Stop playing an effect.
+    pub fn stop_effect(&self, id: i16) -> Result<(), Error> {
+        self.write_ff_event(id as u16, 0)
+    }
+
+    /// Set the overall strength of the force feedback effects, from 0 to
+    /// 0xFFFF. The device must support `FF_GAIN`.
+    pub fn set_ff_gain(&self, gain: u16) -> Result<(), Error> {
+        self.write_ff_event(EV_FF::FF_GAIN as u16, gain as i32)
+    }
+
+    /// Set the strength of the autocenter force, from 0 (disabled) to 0xFFFF.
+    /// The device must support `FF_AUTOCENTER`.
+    pub fn set_ff_autocenter(&self, autocenter: u16) -> Result<(), Error> {
+        self.write_ff_event(EV_FF::FF_AUTOCENTER as u16, autocenter as i32)
+    }
+
+    /// Return how many force feedback effects the device can hold at once,
+    /// through a kernel EVIOCGEFFECTS.
+    pub fn ff_effects_max(&self) -> Result<i32, Error> {
+        let mut max: c_int = 0;
+        unsafe {
+            ioctl::eviocgeffects(self.as_raw_fd(), &mut max)
+                .map_err(|e| self.error(Error::nix("EVIOCGEFFECTS", e)))?;
+        }
+
+        Ok(max)
+    }
+
+    fn write_ff_event(&self, code: u16, value: i32) -> Result<(), Error> {
+        let file = self._file.as_ref().ok_or(Error::os("write", Errno::EBADF))?;
+        let event = libc::input_event {
+            time: libc::timeval { tv_sec: 0, tv_usec: 0 },
+            type_: EventType::EV_FF as u16,
+            code,
+            value,
+        };
+
+        write_struct(file, &event).map_err(|e| self.error(e))
+    }
+
+    /// Attach the path of the device node to an error of the device.
+    fn error(&self, error: Error) -> Error {
+        error.with_fd_path(self._file.as_ref().map(AsRawFd::as_raw_fd))
+    }
+}
+
+fn keymap_entry_for(scancode: &[u8]) -> Result<libc::input_keymap_entry, Error> {
+    let mut entry: libc::input_keymap_entry = unsafe { mem::zeroed() };
+    if scancode.is_empty() || scancode.len() > entry.scancode.len() {
+        return Err(Error::InvalidCode(format!("scancode of {} bytes", scancode.len())));
+    }
+    entry.len = scancode.len() as u8;
+    entry.scancode[..scancode.len()].copy_from_slice(scancode);
+    Ok(entry)
+}
+
+fn keymap_entry_from_raw(entry: &libc::input_keymap_entry) -> KeymapEntry {
+    let len = (entry.len as usize).min(entry.scancode.len());
+    KeymapEntry {
+        index: entry.index,
+        scancode: entry.scancode[..len].to_vec(),
+        keycode: int_to_event_code(EventType::EV_KEY as c_uint, entry.keycode),
+    }
+}
+
+impl AsRawFd for Device {
+    /// Returns the file descriptor set with `set_fd`, or `-1` if there is none.
+    fn as_raw_fd(&self) -> RawFd {
+        match self._file {
+            Some(ref file) => file.as_raw_fd(),
+            None => -1,
         }
     }
 }
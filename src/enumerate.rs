@@ -0,0 +1,207 @@
+//! Discovery of the evdev devices present on the system.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use evdev_rs::Enumerator;
+//! use evdev_rs::enums::{EventCode, EV_KEY};
+//!
+//! let keyboards = Enumerator::new()
+//!     .has(EventCode::EV_KEY(EV_KEY::KEY_A))
+//!     .scan()
+//!     .unwrap();
+//!
+//! for (path, device) in keyboards.devices.iter() {
+//!     println!("{}: {}", path.display(), device.name().unwrap_or(""));
+//! }
+//! for (path, error) in keyboards.skipped.iter() {
+//!     println!("{}: skipped ({})", path.display(), error);
+//! }
+//! ```
+
+use device::{Capability, Device};
+use error::Error;
+use std::ffi::OsStr;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+
+/// The directory holding the evdev device nodes.
+pub const DEV_INPUT_PATH: &str = "/dev/input";
+
+/// The result of walking the evdev device nodes.
+pub struct Enumeration {
+    /// The devices that could be opened and matched every filter, ordered
+    /// by their event number.
+    pub devices: Vec<(PathBuf, Device)>,
+    /// The nodes that could not be opened, together with the reason.
+    ///
+    /// This usually means that the caller lacks the permission to read the
+    /// node (`EACCES`) or that the device was unplugged while scanning
+    /// (`Error::DeviceGone` or `ENOENT`).
+    pub skipped: Vec<(PathBuf, Error)>,
+}
+
+impl IntoIterator for Enumeration {
+    type Item = (PathBuf, Device);
+    type IntoIter = ::std::vec::IntoIter<(PathBuf, Device)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.devices.into_iter()
+    }
+}
+
+/// Walks `/dev/input/event*` and opens every node it finds.
+///
+/// Filters added to the enumerator are applied to each opened device, and
+/// only the devices matching all of them are returned.
+pub struct Enumerator {
+    path: PathBuf,
+    name: Option<String>,
+    bustype: Option<u16>,
+    vendor_id: Option<u16>,
+    product_id: Option<u16>,
+    version: Option<u16>,
+    capabilities: Vec<Box<dyn Capability>>,
+}
+
+impl Enumerator {
+    /// Create an enumerator for the nodes in `/dev/input` without any filter.
+    pub fn new() -> Enumerator {
+        Enumerator::with_path(DEV_INPUT_PATH)
+    }
+
+    /// Create an enumerator for the `event*` nodes in the given directory.
+    pub fn with_path<P: AsRef<Path>>(path: P) -> Enumerator {
+        Enumerator {
+            path: path.as_ref().to_path_buf(),
+            name: None,
+            bustype: None,
+            vendor_id: None,
+            product_id: None,
+            version: None,
+            capabilities: Vec::new(),
+        }
+    }
+
+    /// Only match devices with exactly this name.
+    pub fn name(mut self, name: &str) -> Enumerator {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    /// Only match devices with this bus type.
+    pub fn bustype(mut self, bustype: u16) -> Enumerator {
+        self.bustype = Some(bustype);
+        self
+    }
+
+    /// Only match devices with this vendor id.
+    pub fn vendor_id(mut self, vendor_id: u16) -> Enumerator {
+        self.vendor_id = Some(vendor_id);
+        self
+    }
+
+    /// Only match devices with this product id.
+    pub fn product_id(mut self, product_id: u16) -> Enumerator {
+        self.product_id = Some(product_id);
+        self
+    }
+
+    /// Only match devices with this version.
+    pub fn version(mut self, version: u16) -> Enumerator {
+        self.version = Some(version);
+        self
+    }
+
+    /// Only match devices supporting the InputProp/EventType/EventCode, as
+    /// reported by `Device::has`.
+    ///
+    /// This may be called several times, in which case a device must support
+    /// all of the given capabilities.
+    pub fn has<C: Capability + 'static>(mut self, capability: C) -> Enumerator {
+        self.capabilities.push(Box::new(capability));
+        self
+    }
+
+    /// Returns `true` if the device matches every filter of this enumerator.
+    pub fn matches(&self, device: &Device) -> bool {
+        if let Some(ref name) = self.name {
+            if device.name() != Some(name.as_str()) {
+                return false;
+            }
+        }
+
+        let ids = [(self.bustype, device.bustype()),
+                   (self.vendor_id, device.vendor_id()),
+                   (self.product_id, device.product_id()),
+                   (self.version, device.version())];
+        if ids.iter().any(|&(wanted, id)| wanted.map_or(false, |w| w != id)) {
+            return false;
+        }
+
+        self.capabilities.iter().all(|cap| device.has(&**cap))
+    }
+
+    /// Open every `event*` node and return the ones matching the filters.
+    ///
+    /// Nodes that cannot be opened do not make the scan fail, they are
+    /// reported in `Enumeration::skipped` instead. An error is only returned
+    /// if the directory itself cannot be read.
+    pub fn scan(&self) -> Result<Enumeration, Error> {
+        let mut nodes = Vec::new();
+        let read_error = |e| Error::io("read_dir", e).with_path(Some(&self.path));
+        for entry in fs::read_dir(&self.path).map_err(read_error)? {
+            let entry = entry.map_err(read_error)?;
+            if let Some(number) = event_node_number(&entry.file_name()) {
+                nodes.push((number, entry.path()));
+            }
+        }
+        nodes.sort();
+
+        let mut enumeration = Enumeration {
+            devices: Vec::new(),
+            skipped: Vec::new(),
+        };
+
+        for (_, path) in nodes {
+            let device = File::open(&path)
+                .map_err(|e| Error::io("open", e).with_path(Some(&path)))
+                .and_then(Device::new_from_fd);
+
+            match device {
+                Ok(device) => {
+                    if self.matches(&device) {
+                        enumeration.devices.push((path, device));
+                    }
+                },
+                Err(error) => {
+                    debug!("skipping {}: {}", path.display(), error);
+                    enumeration.skipped.push((path, error));
+                },
+            }
+        }
+
+        Ok(enumeration)
+    }
+}
+
+impl Default for Enumerator {
+    fn default() -> Enumerator {
+        Enumerator::new()
+    }
+}
+
+/// Open every evdev device in `/dev/input`.
+///
+/// This is a shortcut for `Enumerator::new().scan()`.
+pub fn enumerate() -> Result<Enumeration, Error> {
+    Enumerator::new().scan()
+}
+
+/// Returns N for a device node named `eventN`, and `None` for any other name.
+pub(crate) fn event_node_number(name: &OsStr) -> Option<u32> {
+    name.to_str()
+        .and_then(|n| n.strip_prefix("event"))
+        .and_then(|n| n.parse::<u32>().ok())
+}
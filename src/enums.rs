@@ -19,6 +19,11 @@ pub enum EventType {
     EV_MAX = 31,
 }
 
+impl EventType {
+    /// The other names of the values, with the values they stand for.
+    pub const ALIASES: &'static [(&'static str, EventType)] = &[];
+}
+
 pub fn int_to_event_type(code: u32) -> Option<EventType> {
     match code {
         0 => Some(EventType::EV_SYN),
@@ -77,6 +82,11 @@ pub enum EV_REL {
     REL_MAX = 15,
 }
 
+impl EV_REL {
+    /// The other names of the values, with the values they stand for.
+    pub const ALIASES: &'static [(&'static str, EV_REL)] = &[];
+}
+
 pub fn int_to_ev_rel(code: u32) -> Option<EV_REL> {
     match code {
         0 => Some(EV_REL::REL_X),
@@ -145,6 +155,11 @@ pub enum EV_ABS {
     ABS_MAX = 63,
 }
 
+impl EV_ABS {
+    /// The other names of the values, with the values they stand for.
+    pub const ALIASES: &'static [(&'static str, EV_ABS)] = &[];
+}
+
 pub fn int_to_ev_abs(code: u32) -> Option<EV_ABS> {
     match code {
         0 => Some(EV_ABS::ABS_X),
@@ -746,6 +761,53 @@ pub enum EV_KEY {
     BTN_GEAR_UP = 337,
 }
 
+impl EV_KEY {
+    pub const KEY_MIN_INTERESTING: EV_KEY = EV_KEY::KEY_MUTE;
+    pub const KEY_HANGUEL: EV_KEY = EV_KEY::KEY_HANGEUL;
+    pub const KEY_SCREENLOCK: EV_KEY = EV_KEY::KEY_COFFEE;
+    pub const KEY_DIRECTION: EV_KEY = EV_KEY::KEY_ROTATE_DISPLAY;
+    pub const KEY_BRIGHTNESS_ZERO: EV_KEY = EV_KEY::KEY_BRIGHTNESS_AUTO;
+    pub const KEY_WIMAX: EV_KEY = EV_KEY::KEY_WWAN;
+    pub const KEY_ZOOM: EV_KEY = EV_KEY::KEY_FULL_SCREEN;
+    pub const KEY_SCREEN: EV_KEY = EV_KEY::KEY_ASPECT_RATIO;
+    pub const KEY_BRIGHTNESS_TOGGLE: EV_KEY = EV_KEY::KEY_DISPLAYTOGGLE;
+    pub const BTN_TRIGGER_HAPPY: EV_KEY = EV_KEY::BTN_TRIGGER_HAPPY1;
+    pub const BTN_MISC: EV_KEY = EV_KEY::BTN_0;
+    pub const BTN_MOUSE: EV_KEY = EV_KEY::BTN_LEFT;
+    pub const BTN_JOYSTICK: EV_KEY = EV_KEY::BTN_TRIGGER;
+    pub const BTN_A: EV_KEY = EV_KEY::BTN_SOUTH;
+    pub const BTN_GAMEPAD: EV_KEY = EV_KEY::BTN_SOUTH;
+    pub const BTN_B: EV_KEY = EV_KEY::BTN_EAST;
+    pub const BTN_X: EV_KEY = EV_KEY::BTN_NORTH;
+    pub const BTN_Y: EV_KEY = EV_KEY::BTN_WEST;
+    pub const BTN_DIGI: EV_KEY = EV_KEY::BTN_TOOL_PEN;
+    pub const BTN_WHEEL: EV_KEY = EV_KEY::BTN_GEAR_DOWN;
+
+    /// The other names of the values, with the values they stand for.
+    pub const ALIASES: &'static [(&'static str, EV_KEY)] = &[
+        ("KEY_MIN_INTERESTING", EV_KEY::KEY_MUTE),
+        ("KEY_HANGUEL", EV_KEY::KEY_HANGEUL),
+        ("KEY_SCREENLOCK", EV_KEY::KEY_COFFEE),
+        ("KEY_DIRECTION", EV_KEY::KEY_ROTATE_DISPLAY),
+        ("KEY_BRIGHTNESS_ZERO", EV_KEY::KEY_BRIGHTNESS_AUTO),
+        ("KEY_WIMAX", EV_KEY::KEY_WWAN),
+        ("KEY_ZOOM", EV_KEY::KEY_FULL_SCREEN),
+        ("KEY_SCREEN", EV_KEY::KEY_ASPECT_RATIO),
+        ("KEY_BRIGHTNESS_TOGGLE", EV_KEY::KEY_DISPLAYTOGGLE),
+        ("BTN_TRIGGER_HAPPY", EV_KEY::BTN_TRIGGER_HAPPY1),
+        ("BTN_MISC", EV_KEY::BTN_0),
+        ("BTN_MOUSE", EV_KEY::BTN_LEFT),
+        ("BTN_JOYSTICK", EV_KEY::BTN_TRIGGER),
+        ("BTN_A", EV_KEY::BTN_SOUTH),
+        ("BTN_GAMEPAD", EV_KEY::BTN_SOUTH),
+        ("BTN_B", EV_KEY::BTN_EAST),
+        ("BTN_X", EV_KEY::BTN_NORTH),
+        ("BTN_Y", EV_KEY::BTN_WEST),
+        ("BTN_DIGI", EV_KEY::BTN_TOOL_PEN),
+        ("BTN_WHEEL", EV_KEY::BTN_GEAR_DOWN),
+    ];
+}
+
 pub fn int_to_ev_key(code: u32) -> Option<EV_KEY> {
     match code {
         0 => Some(EV_KEY::KEY_RESERVED),
@@ -1316,6 +1378,11 @@ pub enum EV_LED {
     LED_MAX = 15,
 }
 
+impl EV_LED {
+    /// The other names of the values, with the values they stand for.
+    pub const ALIASES: &'static [(&'static str, EV_LED)] = &[];
+}
+
 pub fn int_to_ev_led(code: u32) -> Option<EV_LED> {
     match code {
         0 => Some(EV_LED::LED_NUML),
@@ -1343,6 +1410,11 @@ pub enum EV_SND {
     SND_MAX = 7,
 }
 
+impl EV_SND {
+    /// The other names of the values, with the values they stand for.
+    pub const ALIASES: &'static [(&'static str, EV_SND)] = &[];
+}
+
 pub fn int_to_ev_snd(code: u32) -> Option<EV_SND> {
     match code {
         0 => Some(EV_SND::SND_CLICK),
@@ -1365,6 +1437,11 @@ pub enum EV_MSC {
     MSC_MAX = 7,
 }
 
+impl EV_MSC {
+    /// The other names of the values, with the values they stand for.
+    pub const ALIASES: &'static [(&'static str, EV_MSC)] = &[];
+}
+
 pub fn int_to_ev_msc(code: u32) -> Option<EV_MSC> {
     match code {
         0 => Some(EV_MSC::MSC_SERIAL),
@@ -1400,7 +1477,14 @@ pub enum EV_SW {
 }
 
 impl EV_SW {
+    pub const SW_RADIO: EV_SW = EV_SW::SW_RFKILL_ALL;
     pub const SW_MAX: EV_SW = EV_SW::SW_PEN_INSERTED;
+
+    /// The other names of the values, with the values they stand for.
+    pub const ALIASES: &'static [(&'static str, EV_SW)] = &[
+        ("SW_RADIO", EV_SW::SW_RFKILL_ALL),
+        ("SW_MAX", EV_SW::SW_PEN_INSERTED),
+    ];
 }
 
 pub fn int_to_ev_sw(code: u32) -> Option<EV_SW> {
@@ -1435,6 +1519,11 @@ pub enum EV_SYN {
     SYN_MAX = 15,
 }
 
+impl EV_SYN {
+    /// The other names of the values, with the values they stand for.
+    pub const ALIASES: &'static [(&'static str, EV_SYN)] = &[];
+}
+
 pub fn int_to_ev_syn(code: u32) -> Option<EV_SYN> {
     match code {
         0 => Some(EV_SYN::SYN_REPORT),
@@ -1455,6 +1544,11 @@ pub enum EV_REP {
 
 impl EV_REP {
     pub const REP_MAX: EV_REP = EV_REP::REP_PERIOD;
+
+    /// The other names of the values, with the values they stand for.
+    pub const ALIASES: &'static [(&'static str, EV_REP)] = &[
+        ("REP_MAX", EV_REP::REP_PERIOD),
+    ];
 }
 
 pub fn int_to_ev_rep(code: u32) -> Option<EV_REP> {
@@ -1478,6 +1572,11 @@ pub enum InputProp {
     INPUT_PROP_MAX = 31,
 }
 
+impl InputProp {
+    /// The other names of the values, with the values they stand for.
+    pub const ALIASES: &'static [(&'static str, InputProp)] = &[];
+}
+
 pub fn int_to_input_prop(code: u32) -> Option<InputProp> {
     match code {
         0 => Some(InputProp::INPUT_PROP_POINTER),
@@ -1518,6 +1617,21 @@ pub enum EV_FF {
 
 impl EV_FF {
     pub const FF_STATUS_MAX: EV_FF = EV_FF::FF_STATUS_PLAYING;
+    pub const FF_MAX_EFFECTS: EV_FF = EV_FF::FF_GAIN;
+    pub const FF_EFFECT_MIN: EV_FF = EV_FF::FF_RUMBLE;
+    pub const FF_EFFECT_MAX: EV_FF = EV_FF::FF_RAMP;
+    pub const FF_WAVEFORM_MIN: EV_FF = EV_FF::FF_SQUARE;
+    pub const FF_WAVEFORM_MAX: EV_FF = EV_FF::FF_CUSTOM;
+
+    /// The other names of the values, with the values they stand for.
+    pub const ALIASES: &'static [(&'static str, EV_FF)] = &[
+        ("FF_STATUS_MAX", EV_FF::FF_STATUS_PLAYING),
+        ("FF_MAX_EFFECTS", EV_FF::FF_GAIN),
+        ("FF_EFFECT_MIN", EV_FF::FF_RUMBLE),
+        ("FF_EFFECT_MAX", EV_FF::FF_RAMP),
+        ("FF_WAVEFORM_MIN", EV_FF::FF_SQUARE),
+        ("FF_WAVEFORM_MAX", EV_FF::FF_CUSTOM),
+    ];
 }
 
 pub fn int_to_ev_ff(code: u32) -> Option<EV_FF> {
@@ -1572,6 +1686,11 @@ pub enum BusType {
     BUS_INTEL_ISHTP = 31,
 }
 
+impl BusType {
+    /// The other names of the values, with the values they stand for.
+    pub const ALIASES: &'static [(&'static str, BusType)] = &[];
+}
+
 pub fn int_to_bus_type(code: u32) -> Option<BusType> {
     match code {
         1 => Some(BusType::BUS_PCI),
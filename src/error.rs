@@ -0,0 +1,156 @@
+//! The error type of evdev-rs.
+
+use nix::errno::Errno;
+use std::error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::os::unix::io::RawFd;
+use std::path::PathBuf;
+
+/// An error of an evdev-rs operation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// A system call or libevdev function failed.
+    Os {
+        /// The failed operation, e.g. `EVIOCGREP` or `libevdev_set_fd`.
+        operation: &'static str,
+        /// The device node or file operated on, when known.
+        path: Option<PathBuf>,
+        errno: Errno,
+    },
+    /// The device was unplugged (`ENODEV`). It should be closed.
+    DeviceGone {
+        path: Option<PathBuf>,
+    },
+    /// Nothing can be read without blocking (`EAGAIN`).
+    WouldBlock,
+    /// The device, or the operation, does not support the capability.
+    UnsupportedCapability(String),
+    /// The event type, event code or property is not valid for the
+    /// operation.
+    InvalidCode(String),
+    /// A recording could not be parsed.
+    Parse {
+        /// The line of the error, starting at 1.
+        line: usize,
+        message: String,
+    },
+}
+
+impl Error {
+    /// Returns the `Errno` equivalent to the error, if any.
+    pub fn errno(&self) -> Option<Errno> {
+        match *self {
+            Error::Os { errno, .. } => Some(errno),
+            Error::DeviceGone { .. } => Some(Errno::ENODEV),
+            Error::WouldBlock => Some(Errno::EAGAIN),
+            _ => None,
+        }
+    }
+
+    /// Returns the path of the device or file operated on, if known.
+    pub fn path(&self) -> Option<&PathBuf> {
+        match *self {
+            Error::Os { ref path, .. } | Error::DeviceGone { ref path } => path.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// The error of `operation` failing with `errno`.
+    pub(crate) fn os(operation: &'static str, errno: Errno) -> Error {
+        match errno {
+            Errno::ENODEV => Error::DeviceGone { path: None },
+            Errno::EAGAIN => Error::WouldBlock,
+            errno => Error::Os { operation, path: None, errno },
+        }
+    }
+
+    /// The error of a libevdev function returning a negative errno.
+    pub(crate) fn libevdev(operation: &'static str, result: i32) -> Error {
+        Error::os(operation, Errno::from_i32(-result))
+    }
+
+    pub(crate) fn nix(operation: &'static str, error: nix::Error) -> Error {
+        Error::os(operation, error.as_errno().unwrap_or(Errno::EINVAL))
+    }
+
+    pub(crate) fn io(operation: &'static str, error: io::Error) -> Error {
+        let errno = match error.raw_os_error() {
+            Some(errno) => Errno::from_i32(errno),
+            None => match error.kind() {
+                io::ErrorKind::WouldBlock => Errno::EAGAIN,
+                io::ErrorKind::InvalidInput | io::ErrorKind::InvalidData => Errno::EINVAL,
+                io::ErrorKind::NotFound => Errno::ENOENT,
+                io::ErrorKind::PermissionDenied => Errno::EACCES,
+                _ => Errno::EIO,
+            },
+        };
+        Error::os(operation, errno)
+    }
+
+    /// Attach the path operated on, if the error has none yet.
+    pub(crate) fn with_path<P: Into<PathBuf>>(mut self, new_path: Option<P>) -> Error {
+        if let Error::Os { ref mut path, .. } | Error::DeviceGone { ref mut path } = self {
+            if path.is_none() {
+                *path = new_path.map(Into::into);
+            }
+        }
+        self
+    }
+
+    /// Attach the path of the file open as `fd`, looked up in `/proc`.
+    pub(crate) fn with_fd_path(self, fd: Option<RawFd>) -> Error {
+        match self {
+            Error::Os { path: None, .. } | Error::DeviceGone { path: None } => {
+                let path = fd.and_then(|fd| fs::read_link(format!("/proc/self/fd/{}", fd)).ok());
+                self.with_path(path)
+            },
+            _ => self,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Os { operation, path: Some(ref path), errno } =>
+                write!(f, "{} on {} failed: {}", operation, path.display(), errno.desc()),
+            Error::Os { operation, path: None, errno } =>
+                write!(f, "{} failed: {}", operation, errno.desc()),
+            Error::DeviceGone { path: Some(ref path) } =>
+                write!(f, "device {} is gone", path.display()),
+            Error::DeviceGone { path: None } => write!(f, "device is gone"),
+            Error::WouldBlock => write!(f, "operation would block"),
+            Error::UnsupportedCapability(ref what) => write!(f, "unsupported capability: {}", what),
+            Error::InvalidCode(ref what) => write!(f, "invalid code: {}", what),
+            Error::Parse { line, ref message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::Os { ref errno, .. } => Some(errno),
+            _ => None,
+        }
+    }
+}
+
+impl From<Error> for io::Error {
+    fn from(error: Error) -> io::Error {
+        let kind = match error {
+            Error::WouldBlock => io::ErrorKind::WouldBlock,
+            Error::UnsupportedCapability(_) => io::ErrorKind::Unsupported,
+            Error::InvalidCode(_) => io::ErrorKind::InvalidInput,
+            Error::Parse { .. } => io::ErrorKind::InvalidData,
+            _ => match error.errno() {
+                Some(errno) => io::Error::from_raw_os_error(errno as i32).kind(),
+                None => io::ErrorKind::Other,
+            },
+        };
+        io::Error::new(kind, error)
+    }
+}
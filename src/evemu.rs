@@ -0,0 +1,343 @@
+//! The evemu recording format.
+//!
+//! evemu describes a device with one line per property: `N:` for the name,
+//! `I:` for the id, `P:` and `B:` for the property and event code bitmasks,
+//! `A:` for the axes, `L:` and `S:` for the LED and switch states. Events
+//! follow as `E:` lines. Everything after a `#` is a comment.
+//!
+//! The format has no room for the phys and uniq strings, the current values
+//! of the axes nor the key repeat delay and period. They are left out when
+//! writing a `DeviceDescription`, so a description read back has no phys
+//! nor uniq, axes with a value of 0 and no repeat values in its `state`.
+//! `InputProp` has no variant for unknown properties, so the `P:` bits
+//! unknown to evdev-rs are dropped when reading.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use evdev_rs::{Device, DeviceDescription, ReadFlag};
+//! use evdev_rs::evemu::{self, Recorder};
+//! use std::fs::File;
+//! use std::io;
+//!
+//! let d = Device::new_from_fd(File::open("/dev/input/event0").unwrap()).unwrap();
+//! let stdout = io::stdout();
+//! let mut recorder = Recorder::new(stdout.lock(), &DeviceDescription::from_device(&d)).unwrap();
+//! loop {
+//!     let (_, ev) = d.next_event(ReadFlag::NORMAL | ReadFlag::BLOCKING).unwrap();
+//!     recorder.record(&ev).unwrap();
+//! }
+//! ```
+//!
+//! Reading a recording back:
+//!
+//! ```rust,no_run
+//! use evdev_rs::evemu;
+//!
+//! let recording = evemu::load("touchpad.evemu").unwrap();
+//! println!("{}: {} events", recording.description.name, recording.events.len());
+//! ```
+
+use error::Error;
+use libc::c_long;
+use recording::{code_type, codes_of, known_types, DeviceDescription, Recording};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use {AbsInfo, DeviceId, InputEvent, TimeVal};
+
+use enums::*;
+use util::*;
+
+/// The version of the format written.
+const VERSION: &str = "1.3";
+
+/// The number of bytes of a bitmask line.
+const BYTES_PER_LINE: usize = 8;
+
+/// Write the description of a device, without the parts evemu has no room
+/// for.
+pub fn write_description<W: Write>(out: &mut W, description: &DeviceDescription)
+                                   -> Result<(), Error> {
+    let id = &description.id;
+    let mut text = format!("# EVEMU {}\n", VERSION);
+    text += &format!("# Input device name: \"{}\"\n", description.name);
+    text += &format!("# Input device ID: bus {:#04x} vendor {:#04x} product {:#04x} version {:#04x}\n",
+                     id.bustype.clone() as u16, id.vendor, id.product, id.version);
+    text += &format!("N: {}\n", description.name);
+    text += &format!("I: {:04x} {:04x} {:04x} {:04x}\n",
+                     id.bustype.clone() as u16, id.vendor, id.product, id.version);
+
+    let props: Vec<u32> = description.properties.iter().map(|prop| prop.clone() as u32).collect();
+    text += &mask_lines("P:", &props, InputProp::INPUT_PROP_MAX as u32);
+
+    let types: Vec<u32> = description.types.iter()
+        .filter(|&ev_type| *ev_type != EventType::EV_UNK)
+        .map(|ev_type| ev_type.clone() as u32)
+        .collect();
+    text += &mask_lines("B: 00", &types, EventType::EV_MAX as u32);
+    for ev_type in known_types().filter(|ev_type| *ev_type != EventType::EV_SYN) {
+        let max = match EventType::get_max(&ev_type) {
+            Some(max) => max as u32,
+            None => continue,
+        };
+        let codes: Vec<u32> = description.codes.iter()
+            .filter(|code| code_type(code) == ev_type)
+            .map(|code| event_code_to_int(code).1)
+            .collect();
+        text += &mask_lines(&format!("B: {:02x}", ev_type as u32), &codes, max);
+    }
+
+    for (abs, info) in &description.absinfo {
+        text += &format!("A: {:02x} {} {} {} {} {}\n", abs.clone() as u32,
+                         info.minimum, info.maximum, info.fuzz, info.flat, info.resolution);
+    }
+    for (code, value) in &description.state {
+        let tag = match *code {
+            EventCode::EV_LED(_) => "L",
+            EventCode::EV_SW(_) => "S",
+            _ => continue,
+        };
+        text += &format!("{}: {:02x} {}\n", tag, event_code_to_int(code).1, value);
+    }
+
+    out.write_all(text.as_bytes()).map_err(|e| Error::io("write", e))
+}
+
+/// Write an event, with its timestamp as is.
+pub fn write_event<W: Write>(out: &mut W, event: &InputEvent) -> Result<(), Error> {
+    let (ev_type, ev_code) = event_code_to_int(&event.event_code);
+    let comment = match event.event_code {
+        EventCode::EV_SYN(EV_SYN::SYN_REPORT) => "------------ SYN_REPORT (0) ----------".to_string(),
+        ref code => format!("{} / {:<20} {}", event.event_type, code, event.value),
+    };
+    let line = format!("E: {}.{:06} {:04x} {:04x} {:04}\t# {}\n",
+                       event.time.tv_sec, event.time.tv_usec,
+                       ev_type, ev_code, event.value, comment);
+
+    out.write_all(line.as_bytes()).map_err(|e| Error::io("write", e))
+}
+
+/// Write a recording: the description followed by the events.
+pub fn write<W: Write>(out: &mut W, recording: &Recording) -> Result<(), Error> {
+    write_description(out, &recording.description)?;
+    for event in &recording.events {
+        write_event(out, event)?;
+    }
+    Ok(())
+}
+
+/// Records the events of a device as they are read, like `evemu-record`.
+///
+/// Timestamps are written relative to the first event recorded.
+pub struct Recorder<W: Write> {
+    out: W,
+    start: Option<TimeVal>,
+}
+
+impl<W: Write> Recorder<W> {
+    /// Write the description of the device and start recording.
+    pub fn new(mut out: W, description: &DeviceDescription) -> Result<Recorder<W>, Error> {
+        write_description(&mut out, description)?;
+        Ok(Recorder {
+            out,
+            start: None,
+        })
+    }
+
+    /// Write an event.
+    pub fn record(&mut self, event: &InputEvent) -> Result<(), Error> {
+        let start = self.start.get_or_insert_with(|| event.time.clone());
+        let micros = (event.time.tv_sec - start.tv_sec) * 1_000_000
+                     + (event.time.tv_usec - start.tv_usec);
+        let mut event = event.clone();
+        event.time = TimeVal::new(micros.div_euclid(1_000_000), micros.rem_euclid(1_000_000));
+
+        write_event(&mut self.out, &event)
+    }
+
+    /// Returns the writer.
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+/// Parse an evemu recording.
+///
+/// The event codes unknown to evdev-rs are kept as `EventCode::EV_UNK`, the
+/// ones of unknown event types and the unknown properties are dropped.
+pub fn parse(text: &str) -> Result<Recording, Error> {
+    let mut description = DeviceDescription {
+        name: String::new(),
+        phys: None,
+        uniq: None,
+        id: DeviceId {
+            bustype: BusType::BUS_VIRTUAL,
+            vendor: 0,
+            product: 0,
+            version: 0,
+        },
+        types: Vec::new(),
+        codes: Vec::new(),
+        absinfo: Vec::new(),
+        properties: Vec::new(),
+        state: Vec::new(),
+    };
+    let mut props = Vec::new();
+    let mut masks: BTreeMap<u32, Vec<u8>> = BTreeMap::new();
+    let mut events = Vec::new();
+
+    for (number, line) in text.lines().enumerate() {
+        let error = |message: &str| Error::Parse { line: number + 1, message: message.to_string() };
+
+        if let Some(name) = line.strip_prefix("N:") {
+            // The name is taken whole, '#' included.
+            description.name = name.trim().to_string();
+            continue;
+        }
+        let line = match line.find('#') {
+            Some(i) => &line[..i],
+            None => line,
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let (tag, rest) = match line.find(':') {
+            Some(i) => (&line[..i], &line[i + 1..]),
+            None => return Err(error("missing ':'")),
+        };
+        let mut fields = rest.split_whitespace();
+
+        match tag {
+            "I" => {
+                let bustype = hex_field(&mut fields).ok_or_else(|| error("invalid id"))?;
+                description.id = DeviceId {
+                    bustype: int_to_bus_type(bustype).ok_or_else(|| error("unknown bus type"))?,
+                    vendor: hex_field(&mut fields).ok_or_else(|| error("invalid id"))? as u16,
+                    product: hex_field(&mut fields).ok_or_else(|| error("invalid id"))? as u16,
+                    version: hex_field(&mut fields).ok_or_else(|| error("invalid id"))? as u16,
+                };
+            },
+            "P" => {
+                for field in fields {
+                    props.push(u8::from_str_radix(field, 16).map_err(|_| error("invalid mask"))?);
+                }
+            },
+            "B" => {
+                let ev_type = hex_field(&mut fields).ok_or_else(|| error("invalid type"))?;
+                let mask = masks.entry(ev_type).or_default();
+                for field in fields {
+                    mask.push(u8::from_str_radix(field, 16).map_err(|_| error("invalid mask"))?);
+                }
+            },
+            "A" => {
+                let code = hex_field(&mut fields).ok_or_else(|| error("invalid axis"))?;
+                let values = fields.map(|field| field.parse::<i32>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| error("invalid axis"))?;
+                if values.len() < 4 {
+                    return Err(error("invalid axis"));
+                }
+                if let Some(abs) = int_to_ev_abs(code) {
+                    description.absinfo.push((abs, AbsInfo {
+                        value: 0,
+                        minimum: values[0],
+                        maximum: values[1],
+                        fuzz: values[2],
+                        flat: values[3],
+                        // Only written since evemu 1.1.
+                        resolution: values.get(4).cloned().unwrap_or(0),
+                    }));
+                }
+            },
+            "L" | "S" => {
+                let ev_type = if tag == "L" { EventType::EV_LED } else { EventType::EV_SW };
+                let code = hex_field(&mut fields).ok_or_else(|| error("invalid state"))?;
+                let value = fields.next()
+                    .and_then(|field| field.parse().ok())
+                    .ok_or_else(|| error("invalid state"))?;
+                description.state.push((int_to_event_code(ev_type as u32, code), value));
+            },
+            "E" => {
+                let time = fields.next()
+                    .and_then(parse_time)
+                    .ok_or_else(|| error("invalid timestamp"))?;
+                let ev_type = hex_field(&mut fields)
+                    .filter(|&ev_type| ev_type <= EventType::EV_MAX as u32)
+                    .ok_or_else(|| error("invalid event type"))?;
+                let code = hex_field(&mut fields).ok_or_else(|| error("invalid event code"))?;
+                let value = fields.next()
+                    .and_then(|field| field.parse().ok())
+                    .ok_or_else(|| error("invalid event value"))?;
+                events.push(InputEvent::new(&time, &int_to_event_code(ev_type, code), value));
+            },
+            _ => return Err(error("unknown line")),
+        }
+    }
+
+    description.properties = mask_bits(&props)
+        .filter_map(int_to_input_prop)
+        .collect();
+    if let Some(mask) = masks.get(&(EventType::EV_SYN as u32)) {
+        description.types = mask_bits(mask)
+            .filter_map(int_to_event_type)
+            .filter(|ev_type| *ev_type != EventType::EV_UNK)
+            .collect();
+    }
+    for ev_type in known_types().filter(|ev_type| *ev_type != EventType::EV_SYN) {
+        let number = ev_type.clone() as u32;
+        if let Some(mask) = masks.get(&number) {
+            let codes: Vec<_> = codes_of(&ev_type).collect();
+            description.codes.extend(mask_bits(mask).filter_map(|bit| codes.get(bit as usize).cloned()));
+        }
+    }
+
+    Ok(Recording { description, events })
+}
+
+/// Read an evemu recording from a file and parse it with `parse`.
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Recording, Error> {
+    let path = path.as_ref();
+    let text = fs::read_to_string(path).map_err(|e| Error::io("read", e).with_path(Some(path)))?;
+    parse(&text)
+}
+
+/// Format the bitmask with the given bits set as lines of bytes, padded
+/// with zeroes like evemu does.
+fn mask_lines(prefix: &str, bits: &[u32], max: u32) -> String {
+    let len = (max as usize / 8 + 1).div_ceil(BYTES_PER_LINE) * BYTES_PER_LINE;
+    let mut bytes = vec![0u8; len];
+    for &bit in bits {
+        if let Some(byte) = bytes.get_mut(bit as usize / 8) {
+            *byte |= 1 << (bit % 8);
+        }
+    }
+
+    let mut text = String::new();
+    for chunk in bytes.chunks(BYTES_PER_LINE) {
+        text += prefix;
+        for byte in chunk {
+            text += &format!(" {:02x}", byte);
+        }
+        text += "\n";
+    }
+    text
+}
+
+/// Returns the bits set in a bitmask, in ascending order.
+fn mask_bits(mask: &[u8]) -> impl Iterator<Item = u32> + '_ {
+    (0..mask.len() as u32 * 8).filter(move |bit| mask[*bit as usize / 8] & (1 << (bit % 8)) != 0)
+}
+
+fn hex_field<'a, I: Iterator<Item = &'a str>>(fields: &mut I) -> Option<u32> {
+    fields.next().and_then(|field| u32::from_str_radix(field, 16).ok())
+}
+
+/// Parse a `sec.usec` timestamp.
+fn parse_time(field: &str) -> Option<TimeVal> {
+    let (sec, usec) = field.split_once('.')?;
+    Some(TimeVal::new(sec.parse::<c_long>().ok()?, usec.parse::<c_long>().ok()?))
+}
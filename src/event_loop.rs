@@ -0,0 +1,259 @@
+//! Reading from many devices on a single thread.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use evdev_rs::{enumerate, EventLoop, LoopEvent};
+//! use std::time::Duration;
+//!
+//! let mut event_loop = EventLoop::new().unwrap();
+//! for (_, device) in enumerate().unwrap() {
+//!     event_loop.add(device).unwrap();
+//! }
+//! let tick = event_loop.add_timer(Duration::from_secs(1), Some(Duration::from_secs(1)));
+//!
+//! event_loop.run(|_, event| {
+//!     match event {
+//!         LoopEvent::Event(token, ev) => println!("{:?}: {}", token, ev.event_code),
+//!         LoopEvent::Timer(t) if t == tick => println!("tick"),
+//!         _ => (),
+//!     }
+//!     true
+//! }).unwrap();
+//! ```
+
+use device::Device;
+use error::Error;
+use nix::errno::Errno;
+use nix::sys::epoll::{epoll_create1, epoll_ctl, epoll_wait, EpollCreateFlags,
+                      EpollEvent, EpollFlags, EpollOp};
+use nix::unistd::close;
+use std::collections::{HashMap, VecDeque};
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::time::{Duration, Instant};
+use {InputEvent, ReadFlag, ReadStatus};
+
+use util::*;
+
+/// Identifies a device registered with an `EventLoop`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DeviceToken(pub usize);
+
+/// Identifies a timer registered with an `EventLoop`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TimerToken(pub usize);
+
+/// Something that happened in an `EventLoop`.
+pub enum LoopEvent {
+    /// An event read from a device.
+    Event(DeviceToken, InputEvent),
+    /// An event of the state delta following a `SYN_DROPPED`, see
+    /// `ReadStatus::Sync`.
+    Sync(DeviceToken, InputEvent),
+    /// The device went away (`ENODEV`) and was removed from the loop.
+    Removed(DeviceToken, Device),
+    /// A timer expired.
+    Timer(TimerToken),
+}
+
+struct Timer {
+    token: TimerToken,
+    deadline: Instant,
+    interval: Option<Duration>,
+}
+
+/// An epoll based loop reading the events of many devices.
+pub struct EventLoop {
+    epoll: RawFd,
+    devices: HashMap<usize, Device>,
+    next_device: usize,
+    timers: Vec<Timer>,
+    next_timer: usize,
+    queue: VecDeque<LoopEvent>,
+}
+
+impl EventLoop {
+    /// Create an event loop without any device or timer.
+    pub fn new() -> Result<EventLoop, Error> {
+        let epoll = epoll_create1(EpollCreateFlags::EPOLL_CLOEXEC)
+            .map_err(|e| Error::nix("epoll_create1", e))?;
+
+        Ok(EventLoop {
+            epoll,
+            devices: HashMap::new(),
+            next_device: 0,
+            timers: Vec::new(),
+            next_timer: 0,
+            queue: VecDeque::new(),
+        })
+    }
+
+    /// Register a device with the loop.
+    ///
+    /// The device's file descriptor is switched to non-blocking mode.
+    pub fn add(&mut self, device: Device) -> Result<DeviceToken, Error> {
+        let fd = device.as_raw_fd();
+        set_nonblocking(fd)?;
+
+        let token = self.next_device;
+        let mut event = EpollEvent::new(EpollFlags::EPOLLIN, token as u64);
+        epoll_ctl(self.epoll, EpollOp::EpollCtlAdd, fd, &mut event)
+            .map_err(|e| Error::nix("epoll_ctl", e))?;
+
+        self.next_device += 1;
+        self.devices.insert(token, device);
+        Ok(DeviceToken(token))
+    }
+
+    /// Unregister a device from the loop and return it.
+    pub fn remove(&mut self, token: DeviceToken) -> Option<Device> {
+        let device = self.devices.remove(&token.0)?;
+        let _ = epoll_ctl(self.epoll, EpollOp::EpollCtlDel, device.as_raw_fd(), None);
+        Some(device)
+    }
+
+    /// Returns the device registered with this token.
+    pub fn device(&self, token: DeviceToken) -> Option<&Device> {
+        self.devices.get(&token.0)
+    }
+
+    /// Returns the devices registered with the loop.
+    pub fn devices(&self) -> impl Iterator<Item = (DeviceToken, &Device)> {
+        self.devices.iter().map(|(&token, device)| (DeviceToken(token), device))
+    }
+
+    /// Add a timer expiring after `delay`, then every `interval` if given.
+    pub fn add_timer(&mut self, delay: Duration, interval: Option<Duration>) -> TimerToken {
+        let token = TimerToken(self.next_timer);
+        self.next_timer += 1;
+        self.timers.push(Timer {
+            token,
+            deadline: Instant::now() + delay,
+            interval,
+        });
+        token
+    }
+
+    /// Cancel a timer. Returns `false` if the timer already expired or was
+    /// cancelled.
+    pub fn cancel_timer(&mut self, token: TimerToken) -> bool {
+        let len = self.timers.len();
+        self.timers.retain(|timer| timer.token != token);
+        self.timers.len() != len
+    }
+
+    /// Wait for the next event of any device or timer.
+    ///
+    /// With a `timeout` of `None` this blocks until an event is available,
+    /// otherwise `Error::WouldBlock` is returned once the timeout expires.
+    pub fn next_event(&mut self, timeout: Option<Duration>) -> Result<LoopEvent, Error> {
+        let give_up = timeout.map(|timeout| Instant::now() + timeout);
+
+        loop {
+            if let Some(event) = self.queue.pop_front() {
+                return Ok(event);
+            }
+
+            let now = Instant::now();
+            if let Some(token) = self.fire_timer(now) {
+                return Ok(LoopEvent::Timer(token));
+            }
+
+            let next_timer = self.timers.iter().map(|timer| timer.deadline).min();
+            let wake = match (next_timer, give_up) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+            if give_up.is_some_and(|give_up| give_up <= now) {
+                return Err(Error::WouldBlock);
+            }
+
+            // Round up so that we don't wake up right before the deadline.
+            let timeout_ms = wake.map_or(-1, |wake| {
+                (wake.saturating_duration_since(now).as_micros() as isize + 999) / 1000
+            });
+
+            let mut events = [EpollEvent::empty(); 16];
+            let n = match epoll_wait(self.epoll, &mut events, timeout_ms) {
+                Ok(n) => n,
+                Err(nix::Error::Sys(Errno::EINTR)) => continue,
+                Err(error) => return Err(Error::nix("epoll_wait", error)),
+            };
+
+            for event in &events[..n] {
+                self.read_device(event.data() as usize)?;
+            }
+        }
+    }
+
+    /// Run the loop, calling `callback` for every event until it returns
+    /// `false`.
+    pub fn run<F>(&mut self, mut callback: F) -> Result<(), Error>
+        where F: FnMut(&mut EventLoop, LoopEvent) -> bool
+    {
+        loop {
+            let event = self.next_event(None)?;
+            if !callback(self, event) {
+                return Ok(());
+            }
+        }
+    }
+
+    fn fire_timer(&mut self, now: Instant) -> Option<TimerToken> {
+        let index = self.timers.iter().position(|timer| timer.deadline <= now)?;
+        let token = self.timers[index].token;
+
+        match self.timers[index].interval {
+            Some(interval) => self.timers[index].deadline += interval,
+            None => { self.timers.remove(index); },
+        }
+
+        Some(token)
+    }
+
+    /// Read all the events available from a device, libevdev's internal
+    /// queue included.
+    fn read_device(&mut self, token: usize) -> Result<(), Error> {
+        let mut flags = ReadFlag::NORMAL;
+
+        loop {
+            let result = match self.devices.get(&token) {
+                Some(device) => device.next_event(flags),
+                None => return Ok(()),
+            };
+
+            match result {
+                Ok((ReadStatus::Success, event)) => {
+                    self.queue.push_back(LoopEvent::Event(DeviceToken(token), event));
+                },
+                Ok((ReadStatus::Sync, event)) => {
+                    flags = ReadFlag::SYNC;
+                    self.queue.push_back(LoopEvent::Sync(DeviceToken(token), event));
+                },
+                Err(Error::WouldBlock) if flags == ReadFlag::SYNC => flags = ReadFlag::NORMAL,
+                Err(Error::WouldBlock) => return Ok(()),
+                Err(Error::DeviceGone { .. }) => {
+                    if let Some(device) = self.remove(DeviceToken(token)) {
+                        self.queue.push_back(LoopEvent::Removed(DeviceToken(token), device));
+                    }
+                    return Ok(());
+                },
+                Err(error) => return Err(error),
+            }
+        }
+    }
+}
+
+impl Iterator for EventLoop {
+    type Item = Result<LoopEvent, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.next_event(None))
+    }
+}
+
+impl Drop for EventLoop {
+    fn drop(&mut self) {
+        let _ = close(self.epoll);
+    }
+}
@@ -0,0 +1,350 @@
+//! Force feedback effects, as uploaded with `Device::upload_effect`.
+//!
+//! The structs mirror the kernel's `struct ff_effect` and its members, see
+//! `linux/input.h` for the meaning and units of each field.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use evdev_rs::Device;
+//! use evdev_rs::ff::{Effect, EffectKind, Replay};
+//! use std::fs::OpenOptions;
+//!
+//! let f = OpenOptions::new().read(true).write(true).open("/dev/input/event0").unwrap();
+//! let d = Device::new_from_fd(f).unwrap();
+//!
+//! let mut effect = Effect::new(EffectKind::Rumble {
+//!     strong_magnitude: 0x8000,
+//!     weak_magnitude: 0,
+//! });
+//! effect.replay = Replay { length: 500, delay: 0 };
+//!
+//! let id = d.upload_effect(&effect).unwrap();
+//! d.play_effect(id, 1).unwrap();
+//! ```
+
+use libc;
+use std::mem;
+
+use enums::*;
+
+/// Scheduling of an effect.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Replay {
+    /// Duration of the effect in milliseconds, 0 for infinite.
+    pub length: u16,
+    /// Delay in milliseconds before the effect starts playing.
+    pub delay: u16,
+}
+
+/// What triggers an effect.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Trigger {
+    /// The `EV_KEY` code of the button triggering the effect, 0 for none.
+    pub button: u16,
+    /// Minimum time in milliseconds between two triggers.
+    pub interval: u16,
+}
+
+/// Shape of the start and end of an effect.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Envelope {
+    /// Duration of the attack in milliseconds.
+    pub attack_length: u16,
+    /// Level at the beginning of the attack.
+    pub attack_level: u16,
+    /// Duration of the fade in milliseconds.
+    pub fade_length: u16,
+    /// Level at the end of the fade.
+    pub fade_level: u16,
+}
+
+/// Parameters of a condition effect for one axis.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Condition {
+    /// Maximum level when the joystick is moved all the way to the right.
+    pub right_saturation: u16,
+    /// Same for the left side.
+    pub left_saturation: u16,
+    /// How fast the force grows when the joystick moves to the right.
+    pub right_coeff: i16,
+    /// Same for the left side.
+    pub left_coeff: i16,
+    /// Size of the dead zone, where no force is produced.
+    pub deadband: u16,
+    /// Position of the dead zone.
+    pub center: i16,
+}
+
+/// The waveform of a periodic effect.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Waveform {
+    Square,
+    Triangle,
+    Sine,
+    SawUp,
+    SawDown,
+}
+
+impl Waveform {
+    /// The `EV_FF` code of this waveform.
+    pub fn code(&self) -> EV_FF {
+        match *self {
+            Waveform::Square => EV_FF::FF_SQUARE,
+            Waveform::Triangle => EV_FF::FF_TRIANGLE,
+            Waveform::Sine => EV_FF::FF_SINE,
+            Waveform::SawUp => EV_FF::FF_SAW_UP,
+            Waveform::SawDown => EV_FF::FF_SAW_DOWN,
+        }
+    }
+
+    fn from_code(code: u16) -> Option<Waveform> {
+        match int_to_ev_ff(code as u32)? {
+            EV_FF::FF_SQUARE => Some(Waveform::Square),
+            EV_FF::FF_TRIANGLE => Some(Waveform::Triangle),
+            EV_FF::FF_SINE => Some(Waveform::Sine),
+            EV_FF::FF_SAW_UP => Some(Waveform::SawUp),
+            EV_FF::FF_SAW_DOWN => Some(Waveform::SawDown),
+            _ => None,
+        }
+    }
+}
+
+/// The type of an effect and its type specific parameters.
+///
+/// Condition effects take the parameters of the X and Y axes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EffectKind {
+    Rumble {
+        strong_magnitude: u16,
+        weak_magnitude: u16,
+    },
+    Periodic {
+        waveform: Waveform,
+        period: u16,
+        magnitude: i16,
+        offset: i16,
+        phase: u16,
+        envelope: Envelope,
+    },
+    Constant {
+        level: i16,
+        envelope: Envelope,
+    },
+    Ramp {
+        start_level: i16,
+        end_level: i16,
+        envelope: Envelope,
+    },
+    Spring([Condition; 2]),
+    Friction([Condition; 2]),
+    Damper([Condition; 2]),
+    Inertia([Condition; 2]),
+}
+
+impl EffectKind {
+    /// The `EV_FF` code of this effect type.
+    pub fn code(&self) -> EV_FF {
+        match *self {
+            EffectKind::Rumble { .. } => EV_FF::FF_RUMBLE,
+            EffectKind::Periodic { .. } => EV_FF::FF_PERIODIC,
+            EffectKind::Constant { .. } => EV_FF::FF_CONSTANT,
+            EffectKind::Ramp { .. } => EV_FF::FF_RAMP,
+            EffectKind::Spring(_) => EV_FF::FF_SPRING,
+            EffectKind::Friction(_) => EV_FF::FF_FRICTION,
+            EffectKind::Damper(_) => EV_FF::FF_DAMPER,
+            EffectKind::Inertia(_) => EV_FF::FF_INERTIA,
+        }
+    }
+}
+
+/// A force feedback effect.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Effect {
+    /// The id assigned by the kernel, or -1 to upload a new effect.
+    pub id: i16,
+    /// Direction of the effect, 0x0000 is down, 0x4000 left, 0x8000 up and
+    /// 0xC000 right.
+    pub direction: u16,
+    pub trigger: Trigger,
+    pub replay: Replay,
+    pub kind: EffectKind,
+}
+
+impl Effect {
+    /// A new effect, to be uploaded, with default direction, trigger and
+    /// replay.
+    pub fn new(kind: EffectKind) -> Effect {
+        Effect {
+            id: -1,
+            direction: 0,
+            trigger: Trigger::default(),
+            replay: Replay::default(),
+            kind,
+        }
+    }
+
+    /// Convert a kernel `ff_effect`. Returns `None` for effect types not
+    /// covered by `EffectKind`, like custom periodic effects.
+    pub fn from_raw(effect: &libc::ff_effect) -> Option<Effect> {
+        let u = &effect.u as *const _;
+        let kind = unsafe {
+            match int_to_ev_ff(effect.type_ as u32)? {
+                EV_FF::FF_RUMBLE => {
+                    let r = &*(u as *const libc::ff_rumble_effect);
+                    EffectKind::Rumble {
+                        strong_magnitude: r.strong_magnitude,
+                        weak_magnitude: r.weak_magnitude,
+                    }
+                },
+                EV_FF::FF_PERIODIC => {
+                    let p = &*(u as *const libc::ff_periodic_effect);
+                    EffectKind::Periodic {
+                        waveform: Waveform::from_code(p.waveform)?,
+                        period: p.period,
+                        magnitude: p.magnitude,
+                        offset: p.offset,
+                        phase: p.phase,
+                        envelope: envelope_from_raw(&p.envelope),
+                    }
+                },
+                EV_FF::FF_CONSTANT => {
+                    let c = &*(u as *const libc::ff_constant_effect);
+                    EffectKind::Constant {
+                        level: c.level,
+                        envelope: envelope_from_raw(&c.envelope),
+                    }
+                },
+                EV_FF::FF_RAMP => {
+                    let r = &*(u as *const libc::ff_ramp_effect);
+                    EffectKind::Ramp {
+                        start_level: r.start_level,
+                        end_level: r.end_level,
+                        envelope: envelope_from_raw(&r.envelope),
+                    }
+                },
+                code => {
+                    let c = &*(u as *const [libc::ff_condition_effect; 2]);
+                    let conditions = [condition_from_raw(&c[0]), condition_from_raw(&c[1])];
+                    match code {
+                        EV_FF::FF_SPRING => EffectKind::Spring(conditions),
+                        EV_FF::FF_FRICTION => EffectKind::Friction(conditions),
+                        EV_FF::FF_DAMPER => EffectKind::Damper(conditions),
+                        EV_FF::FF_INERTIA => EffectKind::Inertia(conditions),
+                        _ => return None,
+                    }
+                },
+            }
+        };
+
+        Some(Effect {
+            id: effect.id,
+            direction: effect.direction,
+            trigger: Trigger {
+                button: effect.trigger.button,
+                interval: effect.trigger.interval,
+            },
+            replay: Replay {
+                length: effect.replay.length,
+                delay: effect.replay.delay,
+            },
+            kind,
+        })
+    }
+
+    pub fn as_raw(&self) -> libc::ff_effect {
+        let mut effect: libc::ff_effect = unsafe { mem::zeroed() };
+        effect.type_ = self.kind.code() as u16;
+        effect.id = self.id;
+        effect.direction = self.direction;
+        effect.trigger = libc::ff_trigger {
+            button: self.trigger.button,
+            interval: self.trigger.interval,
+        };
+        effect.replay = libc::ff_replay {
+            length: self.replay.length,
+            delay: self.replay.delay,
+        };
+
+        let u = &mut effect.u as *mut _;
+        unsafe {
+            match self.kind {
+                EffectKind::Rumble { strong_magnitude, weak_magnitude } => {
+                    *(u as *mut libc::ff_rumble_effect) = libc::ff_rumble_effect {
+                        strong_magnitude,
+                        weak_magnitude,
+                    };
+                },
+                EffectKind::Periodic { waveform, period, magnitude, offset, phase, envelope } => {
+                    let p = &mut *(u as *mut libc::ff_periodic_effect);
+                    p.waveform = waveform.code() as u16;
+                    p.period = period;
+                    p.magnitude = magnitude;
+                    p.offset = offset;
+                    p.phase = phase;
+                    p.envelope = envelope_as_raw(&envelope);
+                },
+                EffectKind::Constant { level, envelope } => {
+                    *(u as *mut libc::ff_constant_effect) = libc::ff_constant_effect {
+                        level,
+                        envelope: envelope_as_raw(&envelope),
+                    };
+                },
+                EffectKind::Ramp { start_level, end_level, envelope } => {
+                    *(u as *mut libc::ff_ramp_effect) = libc::ff_ramp_effect {
+                        start_level,
+                        end_level,
+                        envelope: envelope_as_raw(&envelope),
+                    };
+                },
+                EffectKind::Spring(ref c) | EffectKind::Friction(ref c)
+                | EffectKind::Damper(ref c) | EffectKind::Inertia(ref c) => {
+                    *(u as *mut [libc::ff_condition_effect; 2]) =
+                        [condition_as_raw(&c[0]), condition_as_raw(&c[1])];
+                },
+            }
+        }
+
+        effect
+    }
+}
+
+fn envelope_from_raw(envelope: &libc::ff_envelope) -> Envelope {
+    Envelope {
+        attack_length: envelope.attack_length,
+        attack_level: envelope.attack_level,
+        fade_length: envelope.fade_length,
+        fade_level: envelope.fade_level,
+    }
+}
+
+fn envelope_as_raw(envelope: &Envelope) -> libc::ff_envelope {
+    libc::ff_envelope {
+        attack_length: envelope.attack_length,
+        attack_level: envelope.attack_level,
+        fade_length: envelope.fade_length,
+        fade_level: envelope.fade_level,
+    }
+}
+
+fn condition_from_raw(condition: &libc::ff_condition_effect) -> Condition {
+    Condition {
+        right_saturation: condition.right_saturation,
+        left_saturation: condition.left_saturation,
+        right_coeff: condition.right_coeff,
+        left_coeff: condition.left_coeff,
+        deadband: condition.deadband,
+        center: condition.center,
+    }
+}
+
+fn condition_as_raw(condition: &Condition) -> libc::ff_condition_effect {
+    libc::ff_condition_effect {
+        right_saturation: condition.right_saturation,
+        left_saturation: condition.left_saturation,
+        right_coeff: condition.right_coeff,
+        left_coeff: condition.left_coeff,
+        deadband: condition.deadband,
+        center: condition.center,
+    }
+}
@@ -0,0 +1,54 @@
+//! Reading events a frame at a time.
+//!
+//! A frame is the group of events terminated by a `SYN_REPORT`, which the
+//! kernel emits once the state of the device is consistent.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use evdev_rs::Device;
+//! use std::fs::File;
+//!
+//! let d = Device::new_from_fd(File::open("/dev/input/event0").unwrap()).unwrap();
+//!
+//! for frame in d.frames() {
+//!     let frame = frame.unwrap();
+//!     println!("{}.{}: {} events", frame.time.tv_sec, frame.time.tv_usec,
+//!              frame.events.len());
+//! }
+//! ```
+
+use device::Device;
+use error::Error;
+use {InputEvent, ReadFlag, TimeVal};
+
+/// The events of a device between two `SYN_REPORT`s.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame {
+    /// The timestamp of the `SYN_REPORT` ending the frame.
+    pub time: TimeVal,
+    /// The events of the frame, without the `SYN_REPORT`.
+    pub events: Vec<InputEvent>,
+    /// `true` if the frame was not sent by the device but computed by
+    /// libevdev after a `SYN_DROPPED`, to bring the state up to date.
+    pub synthetic: bool,
+}
+
+/// An iterator over the frames of a device, see `Device::frames`.
+pub struct Frames<'a> {
+    pub(crate) device: &'a Device,
+    pub(crate) flags: ReadFlag,
+}
+
+impl<'a> Iterator for Frames<'a> {
+    type Item = Result<Frame, Error>;
+
+    /// Returns the next frame. Ends when the device's fd is non-blocking and
+    /// no complete frame is available.
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.device.next_frame(self.flags) {
+            Err(Error::WouldBlock) => None,
+            result => Some(result),
+        }
+    }
+}
@@ -0,0 +1,66 @@
+//! Kernel ioctls not wrapped by libevdev.
+
+use libc::{c_char, c_int, c_uint, c_ulong};
+use std::mem::size_of;
+
+const EVDEV_IOCTL_BASE: u8 = b'E';
+const UINPUT_IOCTL_BASE: u8 = b'U';
+
+ioctl_read!(eviocgrep, EVDEV_IOCTL_BASE, 0x03, [c_uint; 2]);
+ioctl_write_ptr!(eviocsrep, EVDEV_IOCTL_BASE, 0x03, [c_uint; 2]);
+
+ioctl_read_buf!(eviocgkey, EVDEV_IOCTL_BASE, 0x18, c_ulong);
+ioctl_read_buf!(eviocgled, EVDEV_IOCTL_BASE, 0x19, c_ulong);
+ioctl_read_buf!(eviocgsnd, EVDEV_IOCTL_BASE, 0x1a, c_ulong);
+ioctl_read_buf!(eviocgsw, EVDEV_IOCTL_BASE, 0x1b, c_ulong);
+
+// EVIOCGKEYCODE_V2 is declared as a read but the kernel reads the index or
+// scancode to look up.
+ioctl_readwrite_bad!(eviocgkeycode_v2,
+                     request_code_read!(EVDEV_IOCTL_BASE, 0x04, size_of::<::libc::input_keymap_entry>()),
+                     ::libc::input_keymap_entry);
+ioctl_write_ptr!(eviocskeycode_v2, EVDEV_IOCTL_BASE, 0x04, ::libc::input_keymap_entry);
+
+// EVIOCSFF is declared as a write but the kernel writes the effect id back.
+ioctl_write_ptr_bad!(eviocsff,
+                     request_code_write!(EVDEV_IOCTL_BASE, 0x80, size_of::<::libc::ff_effect>()),
+                     ::libc::ff_effect);
+ioctl_write_int!(eviocrmff, EVDEV_IOCTL_BASE, 0x81);
+ioctl_read!(eviocgeffects, EVDEV_IOCTL_BASE, 0x84, c_int);
+
+ioctl_none!(ui_dev_create, UINPUT_IOCTL_BASE, 1);
+ioctl_none!(ui_dev_destroy, UINPUT_IOCTL_BASE, 2);
+ioctl_write_ptr!(ui_dev_setup, UINPUT_IOCTL_BASE, 3, ::libc::uinput_setup);
+ioctl_write_ptr!(ui_abs_setup, UINPUT_IOCTL_BASE, 4, ::libc::uinput_abs_setup);
+ioctl_read_buf!(ui_get_sysname, UINPUT_IOCTL_BASE, 44, u8);
+ioctl_read!(ui_get_version, UINPUT_IOCTL_BASE, 45, c_uint);
+
+ioctl_write_int!(ui_set_evbit, UINPUT_IOCTL_BASE, 100);
+ioctl_write_int!(ui_set_keybit, UINPUT_IOCTL_BASE, 101);
+ioctl_write_int!(ui_set_relbit, UINPUT_IOCTL_BASE, 102);
+ioctl_write_int!(ui_set_absbit, UINPUT_IOCTL_BASE, 103);
+ioctl_write_int!(ui_set_mscbit, UINPUT_IOCTL_BASE, 104);
+ioctl_write_int!(ui_set_ledbit, UINPUT_IOCTL_BASE, 105);
+ioctl_write_int!(ui_set_sndbit, UINPUT_IOCTL_BASE, 106);
+ioctl_write_int!(ui_set_ffbit, UINPUT_IOCTL_BASE, 107);
+ioctl_write_ptr_bad!(ui_set_phys,
+                     request_code_write!(UINPUT_IOCTL_BASE, 108, size_of::<*const c_char>()),
+                     c_char);
+ioctl_write_int!(ui_set_swbit, UINPUT_IOCTL_BASE, 109);
+ioctl_write_int!(ui_set_propbit, UINPUT_IOCTL_BASE, 110);
+
+ioctl_readwrite!(ui_begin_ff_upload, UINPUT_IOCTL_BASE, 200, ::libc::uinput_ff_upload);
+ioctl_write_ptr!(ui_end_ff_upload, UINPUT_IOCTL_BASE, 201, ::libc::uinput_ff_upload);
+ioctl_readwrite!(ui_begin_ff_erase, UINPUT_IOCTL_BASE, 202, ::libc::uinput_ff_erase);
+ioctl_write_ptr!(ui_end_ff_erase, UINPUT_IOCTL_BASE, 203, ::libc::uinput_ff_erase);
+
+/// Look up a keymap entry by index rather than by scancode.
+pub const INPUT_KEYMAP_BY_INDEX: u8 = 1;
+
+/// The event type of the force feedback requests read from a uinput device.
+pub const EV_UINPUT: u16 = 0x0101;
+pub const UI_FF_UPLOAD: u16 = 1;
+pub const UI_FF_ERASE: u16 = 2;
+
+/// The first uinput version providing `UI_DEV_SETUP` and `UI_ABS_SETUP`.
+pub const UINPUT_VERSION_DEV_SETUP: c_uint = 5;
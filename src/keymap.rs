@@ -0,0 +1,272 @@
+//! Scancode to keycode mapping, as changed by `setkeycodes` or udev's hwdb.
+//!
+//! The keymap of a device is read and changed with `Device::keymap_entries`,
+//! `Device::get_keycode` and `Device::set_keycode`. Scancodes are byte
+//! strings of up to 32 bytes whose meaning is up to the driver; most drivers
+//! take a `u32` in native byte order, as produced by `u32::to_ne_bytes`.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use evdev_rs::Device;
+//! use evdev_rs::keymap;
+//! use std::fs::File;
+//!
+//! let d = Device::new_from_fd(File::open("/dev/input/event0").unwrap()).unwrap();
+//!
+//! let modalias = keymap::modalias(&d);
+//!
+//! for entry in keymap::load_hwdb("/etc/udev/hwdb.d/70-keyboard.hwdb").unwrap() {
+//!     if !entry.matches(&modalias) {
+//!         continue;
+//!     }
+//!     for (scancode, key) in entry.keys {
+//!         d.set_keycode(&scancode.to_ne_bytes(), &key).unwrap();
+//!     }
+//! }
+//! ```
+
+use device::Device;
+use error::Error;
+use recording::{codes_of, known_types};
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+use util::event_code_to_int;
+
+use enums::*;
+
+/// An entry of the keymap of a device.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeymapEntry {
+    /// The position of the entry in the keymap.
+    pub index: u16,
+    /// The scancode, in the driver's format.
+    pub scancode: Vec<u8>,
+    /// The key reported for the scancode, `EV_UNK` if not known to evdev-rs.
+    pub keycode: EventCode,
+}
+
+/// A block of a systemd hwdb file: its match lines and the
+/// `KEYBOARD_KEY_<scancode>=<key>` properties that apply to them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HwdbEntry {
+    /// The match patterns, e.g. `evdev:input:b0003v046DpC52B*`.
+    pub patterns: Vec<String>,
+    /// The scancodes and their keys, in order.
+    pub keys: Vec<(u32, EV_KEY)>,
+}
+
+impl HwdbEntry {
+    /// Whether one of the patterns matches `modalias`, as udev does with
+    /// fnmatch(3).
+    pub fn matches(&self, modalias: &str) -> bool {
+        self.patterns.iter().any(|pattern| glob_match(pattern.as_bytes(), modalias.as_bytes()))
+    }
+}
+
+/// Parse the `KEYBOARD_KEY_<scancode>=<key>` properties of a systemd hwdb
+/// file, grouped by the match lines they follow.
+///
+/// Keys are named like in hwdb, e.g. `leftctrl` or `btn_left`, or given by
+/// number. Properties naming an unknown key are skipped with a warning, and
+/// blocks without any key are left out.
+pub fn parse_hwdb(text: &str) -> Vec<HwdbEntry> {
+    let mut entries = Vec::new();
+    let mut block: Option<HwdbEntry> = None;
+    // Whether the current block already has properties, a match line then
+    // starts a new one.
+    let mut has_properties = false;
+
+    for (number, line) in text.lines().enumerate() {
+        if line.starts_with('#') {
+            continue;
+        }
+        if line.trim().is_empty() {
+            entries.extend(block.take());
+            continue;
+        }
+
+        if !line.starts_with(char::is_whitespace) {
+            match block {
+                Some(ref mut block) if !has_properties => block.patterns.push(line.trim_end().to_string()),
+                _ => {
+                    entries.extend(block.take());
+                    block = Some(HwdbEntry {
+                        patterns: vec![line.trim_end().to_string()],
+                        keys: Vec::new(),
+                    });
+                    has_properties = false;
+                },
+            }
+            continue;
+        }
+
+        let line = line.trim();
+        let block = match block {
+            Some(ref mut block) => block,
+            None => {
+                warn!("hwdb line {}: property without match {}", number + 1, line);
+                continue;
+            },
+        };
+        has_properties = true;
+
+        let property = match line.strip_prefix("KEYBOARD_KEY_") {
+            Some(property) => property,
+            None => continue,
+        };
+        let (scancode, name) = match property.find('=') {
+            Some(i) => (&property[..i], &property[i + 1..]),
+            None => continue,
+        };
+        // A leading '!' asks udev to synthesize a release, not supported.
+        let name = name.trim_start_matches('!');
+
+        let scancode = u32::from_str_radix(scancode, 16).ok();
+        match (scancode, hwdb_key(name)) {
+            (Some(scancode), Some(key)) => block.keys.push((scancode, key)),
+            _ => warn!("hwdb line {}: invalid property {}", number + 1, line),
+        }
+    }
+    entries.extend(block.take());
+
+    entries.retain(|entry| !entry.keys.is_empty());
+    entries
+}
+
+/// Read a systemd hwdb file and parse it with `parse_hwdb`.
+pub fn load_hwdb<P: AsRef<Path>>(path: P) -> Result<Vec<HwdbEntry>, Error> {
+    let path = path.as_ref();
+    let text = fs::read_to_string(path).map_err(|e| Error::io("read", e).with_path(Some(path)))?;
+    Ok(parse_hwdb(&text))
+}
+
+/// The modalias of a device, as matched against the `evdev:input:` lines of
+/// hwdb, e.g. `evdev:input:b0003v046DpC52Be0111-e0,1,4,11,14,k71,72,...`.
+///
+/// The capabilities are listed in the order of kernels before 6.7. Lines
+/// matching on the name or the DMI data, like `evdev:name:` or
+/// `evdev:atkbd:dmi:`, need a modalias built by the caller.
+pub fn modalias(device: &Device) -> String {
+    let mut modalias = format!("evdev:input:b{:04X}v{:04X}p{:04X}e{:04X}-",
+                               device.bustype(), device.vendor_id(),
+                               device.product_id(), device.version());
+
+    modalias.push('e');
+    for ev_type in known_types().filter(|ev_type| device.has_event_type(ev_type)) {
+        let _ = write!(modalias, "{:X},", ev_type as u32);
+    }
+
+    // Keys below KEY_MIN_INTERESTING are left out by the kernel.
+    let sections = [('k', EventType::EV_KEY, EV_KEY::KEY_MIN_INTERESTING as u32),
+                    ('r', EventType::EV_REL, 0),
+                    ('a', EventType::EV_ABS, 0),
+                    ('m', EventType::EV_MSC, 0),
+                    ('l', EventType::EV_LED, 0),
+                    ('s', EventType::EV_SND, 0),
+                    ('f', EventType::EV_FF, 0),
+                    ('w', EventType::EV_SW, 0)];
+    for &(letter, ref ev_type, min) in &sections {
+        modalias.push(letter);
+        for code in codes_of(ev_type) {
+            let (_, number) = event_code_to_int(&code);
+            if number >= min && device.has_event_code(&code) {
+                let _ = write!(modalias, "{:X},", number);
+            }
+        }
+    }
+
+    modalias
+}
+
+/// fnmatch(3) without flags, as used by udev for the hwdb match lines.
+fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    // The pattern after the last '*' and the text position it was tried at.
+    let mut star = None;
+
+    while t < text.len() {
+        let step = match pattern.get(p) {
+            Some(b'*') => {
+                star = Some((p + 1, t));
+                p += 1;
+                continue;
+            },
+            Some(b'?') => Some(1),
+            Some(b'[') => match class_match(&pattern[p + 1..], text[t]) {
+                Some((true, len)) => Some(len + 1),
+                Some((false, _)) => None,
+                // An unterminated bracket is a literal '['.
+                None if text[t] == b'[' => Some(1),
+                None => None,
+            },
+            Some(b'\\') if p + 1 < pattern.len() => {
+                if pattern[p + 1] == text[t] { Some(2) } else { None }
+            },
+            Some(&c) if c == text[t] => Some(1),
+            _ => None,
+        };
+
+        match (step, star) {
+            (Some(len), _) => {
+                p += len;
+                t += 1;
+            },
+            (None, Some((after, tried))) => {
+                star = Some((after, tried + 1));
+                p = after;
+                t = tried + 1;
+            },
+            (None, None) => return false,
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+/// Match `c` against the bracket expression following a '['. Returns whether
+/// it matched and the length of the expression, closing ']' included, or
+/// `None` if the expression isn't terminated.
+fn class_match(class: &[u8], c: u8) -> Option<(bool, usize)> {
+    let negate = matches!(class.first(), Some(b'!') | Some(b'^'));
+    let start = negate as usize;
+    let mut i = start;
+    let mut found = false;
+
+    // A ']' right after the opening bracket is part of the set.
+    while i < class.len() && (class[i] != b']' || i == start) {
+        if i + 2 < class.len() && class[i + 1] == b'-' && class[i + 2] != b']' {
+            found |= class[i] <= c && c <= class[i + 2];
+            i += 3;
+        } else {
+            found |= class[i] == c;
+            i += 1;
+        }
+    }
+
+    if i == class.len() {
+        None
+    } else {
+        Some((found != negate, i + 1))
+    }
+}
+
+/// Look up a key by its hwdb name.
+fn hwdb_key(name: &str) -> Option<EV_KEY> {
+    if let Ok(code) = name.parse::<u32>() {
+        return int_to_ev_key(code);
+    }
+
+    let name = name.to_uppercase();
+    let code = if name.starts_with("BTN_") {
+        EventCode::from_str(&EventType::EV_KEY, &name)
+    } else {
+        EventCode::from_str(&EventType::EV_KEY, &format!("KEY_{}", name))
+    };
+
+    match code {
+        Some(EventCode::EV_KEY(key)) => Some(key),
+        _ => None,
+    }
+}
@@ -0,0 +1,125 @@
+//! Keeping the lock LEDs of several keyboards in sync.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use evdev_rs::{Device, EventLoop, LoopEvent};
+//! use evdev_rs::led::LedController;
+//! use std::fs::OpenOptions;
+//!
+//! let mut event_loop = EventLoop::new().unwrap();
+//! for path in &["/dev/input/event0", "/dev/input/event1"] {
+//!     let f = OpenOptions::new().read(true).write(true).open(path).unwrap();
+//!     event_loop.add(Device::new_from_fd(f).unwrap()).unwrap();
+//! }
+//!
+//! let mut leds = LedController::new();
+//! event_loop.run(|event_loop, event| {
+//!     if let LoopEvent::Event(_, ev) = event {
+//!         if leds.process(&ev) {
+//!             for (_, device) in event_loop.devices() {
+//!                 let _ = leds.apply(device);
+//!             }
+//!         }
+//!     }
+//!     true
+//! }).unwrap();
+//! ```
+
+use bitset::LedSet;
+use device::Device;
+use error::Error;
+use {InputEvent, LedState};
+
+use enums::*;
+
+/// The lock keys and the LEDs they toggle.
+const LOCKS: [(EV_KEY, EV_LED); 3] = [
+    (EV_KEY::KEY_NUMLOCK, EV_LED::LED_NUML),
+    (EV_KEY::KEY_CAPSLOCK, EV_LED::LED_CAPSL),
+    (EV_KEY::KEY_SCROLLLOCK, EV_LED::LED_SCROLLL),
+];
+
+/// Tracks the state of the num, caps and scroll lock LEDs from the events of
+/// several keyboards and sets it on all of them.
+///
+/// Lock key presses toggle the matching LED. `EV_LED` events, as sent when
+/// another client changes the LEDs of a keyboard, set it.
+pub struct LedController {
+    state: LedSet,
+}
+
+impl LedController {
+    /// Start with all the LEDs off.
+    pub fn new() -> LedController {
+        LedController {
+            state: LedSet::new(),
+        }
+    }
+
+    /// Start from the LEDs lit on the device.
+    pub fn from_device(device: &Device) -> Result<LedController, Error> {
+        let lit = device.led_state()?;
+        let mut controller = LedController::new();
+        for (_, led) in LOCKS.iter() {
+            if lit.contains(led) {
+                controller.state.insert(led);
+            }
+        }
+        Ok(controller)
+    }
+
+    /// The lock LEDs lit.
+    pub fn state(&self) -> &LedSet {
+        &self.state
+    }
+
+    /// Follow an event of one of the keyboards. Returns `true` if the state
+    /// of the LEDs changed and should be applied to the keyboards.
+    pub fn process(&mut self, event: &InputEvent) -> bool {
+        match event.event_code {
+            EventCode::EV_KEY(ref key) if event.value == 1 => {
+                match LOCKS.iter().find(|&(lock, _)| lock == key) {
+                    Some((_, led)) => {
+                        if !self.state.remove(led) {
+                            self.state.insert(led);
+                        }
+                        true
+                    },
+                    None => false,
+                }
+            },
+            EventCode::EV_LED(ref led) if LOCKS.iter().any(|(_, l)| l == led) => {
+                if event.value != 0 {
+                    self.state.insert(led)
+                } else {
+                    self.state.remove(led)
+                }
+            },
+            _ => false,
+        }
+    }
+
+    /// Set the lock LEDs of the device to the tracked state. LEDs the device
+    /// does not have are left out.
+    pub fn apply(&self, device: &Device) -> Result<(), Error> {
+        let leds: Vec<_> = LOCKS.iter()
+            .filter(|&(_, led)| device.has_event_code(&EventCode::EV_LED(led.clone())))
+            .map(|(_, led)| {
+                let state = if self.state.contains(led) { LedState::On } else { LedState::Off };
+                (led.clone(), state)
+            })
+            .collect();
+
+        if leds.is_empty() {
+            return Ok(());
+        }
+        device.kernel_set_led_values(&leds)
+    }
+}
+
+impl Default for LedController {
+    fn default() -> LedController {
+        LedController::new()
+    }
+}
@@ -41,18 +41,52 @@
 //! ```
 
 extern crate evdev_sys as raw;
+#[macro_use]
 extern crate nix;
 extern crate libc;
 #[macro_use]
 extern crate bitflags;
 #[macro_use]
 extern crate log;
+#[cfg(feature = "tokio")]
+extern crate futures_core;
+#[cfg(feature = "tokio")]
+extern crate tokio;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
 
 #[macro_use]
 mod macros;
+pub mod bitset;
 pub mod device;
+pub mod enumerate;
+#[cfg(not(feature = "generate-enums"))]
 pub mod enums;
+#[cfg(feature = "generate-enums")]
+pub mod enums {
+    include!(concat!(env!("OUT_DIR"), "/enums.rs"));
+}
+pub mod error;
+pub mod evemu;
+pub mod event_loop;
+pub mod ff;
+pub mod frame;
+mod ioctl;
+pub mod keymap;
+pub mod led;
+pub mod libinput_record;
 pub mod logging;
+pub mod monitor;
+pub mod mt;
+pub mod player;
+pub mod reader;
+pub mod recording;
+pub mod repeat;
+#[cfg(feature = "serde")]
+mod serialize;
+#[cfg(feature = "tokio")]
+pub mod stream;
 pub mod uinput;
 pub mod util;
 
@@ -64,9 +98,31 @@ use enums::*;
 use util::*;
 
 #[doc(inline)]
-pub use device::Device;
+pub use bitset::{AbsSet, BitSet, EventTypeSet, FfSet, KeySet, LedSet, MiscSet, PropertySet, RelSet,
+                 RepeatSet, SoundSet, SwitchSet};
+#[doc(inline)]
+pub use device::{Capability, Device, EnableData};
+#[doc(inline)]
+pub use enumerate::{enumerate, Enumerator};
+#[doc(inline)]
+pub use error::Error;
+#[doc(inline)]
+pub use event_loop::{DeviceToken, EventLoop, LoopEvent, TimerToken};
+#[doc(inline)]
+pub use frame::{Frame, Frames};
+#[doc(inline)]
+pub use monitor::{Monitor, MonitorEvent};
+#[doc(inline)]
+pub use mt::{MtTracker, ProtocolAConverter, TouchEvent};
+#[doc(inline)]
+pub use reader::{Reader, ReaderEvent, SyncPolicy};
+#[doc(inline)]
+pub use recording::{DeviceDescription, Recording};
+#[cfg(feature = "tokio")]
+#[doc(inline)]
+pub use stream::EventStream;
 #[doc(inline)]
-pub use uinput::UInputDevice;
+pub use uinput::{UInputBuilder, UInputDevice, UInputRequest};
 
 pub enum GrabMode {
     /// Grab the device if not currently grabbed
@@ -107,6 +163,8 @@ pub enum LedState {
     Off = raw::LIBEVDEV_LED_OFF as isize,
 }
 
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct DeviceId {
     pub bustype: BusType,
     pub vendor: u16,
@@ -115,6 +173,8 @@ pub struct DeviceId {
 }
 
 /// used by EVIOCGABS/EVIOCSABS ioctls
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct AbsInfo {
     /// latest reported value for the axis
     pub value: i32,
@@ -158,6 +218,7 @@ impl AbsInfo {
 }
 
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct TimeVal {
     pub tv_sec: c_long,
     pub tv_usec: c_long,
@@ -196,6 +257,7 @@ impl TimeVal {
 
 /// The event structure itself
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct InputEvent {
     /// The time at which event occured
     pub time: TimeVal,
@@ -0,0 +1,348 @@
+//! The `libinput record` format.
+//!
+//! `libinput record` writes YAML: a `devices` list where each device has an
+//! `evdev` section with its name, id, event codes, axes and properties, and
+//! an `events` list of frames, each a list of `[sec, usec, type, code,
+//! value]` events. Only the subset of YAML written by libinput is
+//! supported. The `hid`, `udev` and `quirks` sections and the `libinput`
+//! events are skipped.
+//!
+//! # Example
+//!
+//! Replaying a bug report:
+//!
+//! ```rust,no_run
+//! use evdev_rs::libinput_record;
+//! use evdev_rs::player::Player;
+//!
+//! let recordings = libinput_record::load("touchpad.yml").unwrap();
+//! let mut player = Player::new(&recordings[0]).unwrap();
+//! player.run().unwrap();
+//! ```
+
+use error::Error;
+use libc::c_long;
+use recording::{code_type, DeviceDescription, Recording};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use {AbsInfo, DeviceId, InputEvent, TimeVal};
+
+use enums::*;
+use util::*;
+
+/// The version of the format written.
+const VERSION: u32 = 1;
+
+/// Write recordings, one device each.
+///
+/// The format has no room for the phys and uniq strings, the state of the
+/// LEDs and switches nor the repeat values, they are left out. Timestamps
+/// are written as is, `libinput record` makes them relative to the first
+/// event.
+pub fn write<W: Write>(out: &mut W, recordings: &[Recording]) -> Result<(), Error> {
+    let mut text = "# libinput record\n".to_string();
+    text += &format!("version: {}\n", VERSION);
+    text += &format!("ndevices: {}\n", recordings.len());
+    text += "devices:\n";
+    for recording in recordings {
+        text += &device_text(&recording.description);
+        text += &events_text(&recording.events);
+    }
+
+    out.write_all(text.as_bytes()).map_err(|e| Error::io("write", e))
+}
+
+fn device_text(description: &DeviceDescription) -> String {
+    let id = &description.id;
+    let mut text = "- evdev:\n".to_string();
+    text += &format!("    # Name: {}\n", description.name);
+    text += &format!("    # ID: bus {:#x} vendor {:#x} product {:#x} version {:#x}\n",
+                     id.bustype.clone() as u16, id.vendor, id.product, id.version);
+    text += &format!("    name: \"{}\"\n", escape(&description.name));
+    text += &format!("    id: [{}, {}, {}, {}]\n",
+                     id.bustype.clone() as u16, id.vendor, id.product, id.version);
+
+    text += "    codes:\n";
+    for ev_type in &description.types {
+        let codes: Vec<String> = if *ev_type == EventType::EV_SYN {
+            // Implied by the type in the description.
+            (EV_SYN::SYN_REPORT as u32..=EV_SYN::SYN_DROPPED as u32).map(|code| code.to_string()).collect()
+        } else {
+            description.codes.iter()
+                .filter(|code| code_type(code) == *ev_type)
+                .map(|code| event_code_to_int(code).1.to_string())
+                .collect()
+        };
+        text += &format!("      {}: [{}] # {}\n", ev_type.clone() as u32, codes.join(", "), ev_type);
+    }
+
+    text += "    absinfo:\n";
+    for (abs, info) in &description.absinfo {
+        text += &format!("      {}: [{}, {}, {}, {}, {}]\n", abs.clone() as u32,
+                         info.minimum, info.maximum, info.fuzz, info.flat, info.resolution);
+    }
+
+    let props: Vec<String> = description.properties.iter()
+        .map(|prop| (prop.clone() as u32).to_string())
+        .collect();
+    text += &format!("    properties: [{}]\n", props.join(", "));
+    text
+}
+
+/// Format the events as frames, each ended by a `SYN_REPORT`.
+fn events_text(events: &[InputEvent]) -> String {
+    let mut text = "  events:\n".to_string();
+    let mut frame_start = true;
+    let mut last_report: Option<&TimeVal> = None;
+    for event in events {
+        if frame_start {
+            text += "  - evdev:\n";
+            frame_start = false;
+        }
+
+        let (ev_type, ev_code) = event_code_to_int(&event.event_code);
+        let comment = match event.event_code {
+            EventCode::EV_SYN(EV_SYN::SYN_REPORT) => {
+                let millis = last_report.map_or(0, |last| {
+                    ((event.time.tv_sec - last.tv_sec) * 1_000_000
+                     + (event.time.tv_usec - last.tv_usec)) / 1000
+                });
+                last_report = Some(&event.time);
+                frame_start = true;
+                format!("------------ SYN_REPORT (0) ---------- +{}ms", millis)
+            },
+            ref code => format!("{} / {:<20} {}", event.event_type, code, event.value),
+        };
+        text += &format!("    - [{:3}, {:6}, {:3}, {:3}, {:7}] # {}\n",
+                         event.time.tv_sec, event.time.tv_usec, ev_type, ev_code,
+                         event.value, comment);
+    }
+    text
+}
+
+/// Parse a `libinput record` recording, one `Recording` per device.
+///
+/// The event codes and properties unknown to evdev-rs are kept as
+/// `EventCode::EV_UNK`, the ones of unknown event types are dropped.
+pub fn parse(text: &str) -> Result<Vec<Recording>, Error> {
+    let mut recordings = Vec::new();
+    // The keys of the mappings the current line is nested in, with their
+    // indentation.
+    let mut keys: Vec<(usize, &str)> = Vec::new();
+
+    for (number, line) in text.lines().enumerate() {
+        let error = |message: &str| Error::Parse { line: number + 1, message: message.to_string() };
+
+        let line = strip_comment(line).trim_end();
+        let content = line.trim_start();
+        if content.is_empty() {
+            continue;
+        }
+        let indent = line.len() - content.len();
+
+        // A list item belongs to the key at the same indentation, a key to
+        // the one indented less.
+        let (item, content) = match content.strip_prefix('-') {
+            Some(rest) if rest.is_empty() || rest.starts_with(' ') => (true, rest.trim_start()),
+            _ => (false, content),
+        };
+        while keys.last().is_some_and(|&(i, _)| i > indent || (!item && i == indent)) {
+            keys.pop();
+        }
+
+        let path: Vec<&str> = keys.iter().map(|&(_, key)| key).collect();
+        if item && path == ["devices"] {
+            recordings.push(Recording {
+                description: DeviceDescription {
+                    name: String::new(),
+                    phys: None,
+                    uniq: None,
+                    id: DeviceId {
+                        bustype: BusType::BUS_VIRTUAL,
+                        vendor: 0,
+                        product: 0,
+                        version: 0,
+                    },
+                    types: Vec::new(),
+                    codes: Vec::new(),
+                    absinfo: Vec::new(),
+                    properties: Vec::new(),
+                    state: Vec::new(),
+                },
+                events: Vec::new(),
+            });
+        }
+
+        let (key, value) = split_key(content);
+        let mut path = path;
+        if let Some(key) = key {
+            let key_indent = if item { line.len() - content.len() } else { indent };
+            keys.push((key_indent, key));
+            path.push(key);
+        }
+        let value = match value {
+            Some(value) => value,
+            None => continue,
+        };
+        let recording = match recordings.last_mut() {
+            Some(recording) if path.first() == Some(&"devices") => recording,
+            _ => continue,
+        };
+        let description = &mut recording.description;
+
+        match path[1..] {
+            ["evdev", "name"] => description.name = unquote(value),
+            ["evdev", "id"] => {
+                let id = numbers(value).ok_or_else(|| error("invalid id"))?;
+                if id.len() != 4 {
+                    return Err(error("invalid id"));
+                }
+                description.id = DeviceId {
+                    bustype: int_to_bus_type(id[0] as u32).ok_or_else(|| error("unknown bus type"))?,
+                    vendor: id[1] as u16,
+                    product: id[2] as u16,
+                    version: id[3] as u16,
+                };
+            },
+            ["evdev", "codes", ev_type] => {
+                let ev_type = ev_type.parse::<u32>().map_err(|_| error("invalid event type"))?;
+                let codes = numbers(value).ok_or_else(|| error("invalid event codes"))?;
+                let ev_type = match int_to_event_type(ev_type) {
+                    Some(EventType::EV_UNK) | None => continue,
+                    Some(ev_type) => ev_type,
+                };
+                if ev_type != EventType::EV_SYN {
+                    let max = EventType::get_max(&ev_type).unwrap_or(-1) as i64;
+                    description.codes.extend(codes.into_iter()
+                        .filter(|&code| code >= 0 && code <= max)
+                        .map(|code| int_to_event_code(ev_type.clone() as u32, code as u32)));
+                }
+                description.types.push(ev_type);
+            },
+            ["evdev", "absinfo", code] => {
+                let code = code.parse::<u32>().map_err(|_| error("invalid axis"))?;
+                let values = numbers(value).ok_or_else(|| error("invalid axis"))?;
+                if values.len() < 4 {
+                    return Err(error("invalid axis"));
+                }
+                if let Some(abs) = int_to_ev_abs(code) {
+                    description.absinfo.push((abs, AbsInfo {
+                        value: 0,
+                        minimum: values[0] as i32,
+                        maximum: values[1] as i32,
+                        fuzz: values[2] as i32,
+                        flat: values[3] as i32,
+                        resolution: values.get(4).map_or(0, |&res| res as i32),
+                    }));
+                }
+            },
+            ["evdev", "properties"] => {
+                let props = numbers(value).ok_or_else(|| error("invalid properties"))?;
+                description.properties = props.into_iter()
+                    .filter_map(|prop| int_to_input_prop(prop as u32))
+                    .collect();
+            },
+            ["events", "evdev"] if item => {
+                let fields = numbers(value).ok_or_else(|| error("invalid event"))?;
+                if fields.len() != 5 {
+                    return Err(error("invalid event"));
+                }
+                if fields[2] < 0 || fields[2] > EventType::EV_MAX as i64 {
+                    return Err(error("invalid event type"));
+                }
+                let time = TimeVal::new(fields[0] as c_long, fields[1] as c_long);
+                let code = int_to_event_code(fields[2] as u32, fields[3] as u32);
+                recording.events.push(InputEvent::new(&time, &code, fields[4] as i32));
+            },
+            _ => (),
+        }
+    }
+
+    for recording in &mut recordings {
+        let description = &mut recording.description;
+        description.types.sort_by_key(|ev_type| ev_type.clone() as u32);
+        description.codes.sort_by_key(event_code_to_int);
+        description.absinfo.sort_by_key(|(abs, _)| abs.clone() as u32);
+    }
+    Ok(recordings)
+}
+
+/// Read a `libinput record` recording from a file and parse it with
+/// `parse`.
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Vec<Recording>, Error> {
+    let path = path.as_ref();
+    let text = fs::read_to_string(path).map_err(|e| Error::io("read", e).with_path(Some(path)))?;
+    parse(&text)
+}
+
+/// Remove a `#` comment, unless it is in a string.
+fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+    let mut escaped = false;
+    let mut previous = ' ';
+    for (i, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            '#' if !quoted && previous.is_whitespace() => return &line[..i],
+            _ => (),
+        }
+        previous = c;
+    }
+    line
+}
+
+/// Split `key: value` into its key and its value, either of which may be
+/// missing.
+fn split_key(content: &str) -> (Option<&str>, Option<&str>) {
+    if content.starts_with(['[', '{', '"']) {
+        return (None, Some(content));
+    }
+    match content.find(": ") {
+        Some(i) => (Some(&content[..i]), Some(content[i + 2..].trim())),
+        None => match content.strip_suffix(':') {
+            Some(key) => (Some(key), None),
+            None => (None, Some(content)),
+        },
+    }
+}
+
+/// Parse a flow list of decimal or `0x` hexadecimal numbers.
+fn numbers(value: &str) -> Option<Vec<i64>> {
+    let list = value.strip_prefix('[')?.strip_suffix(']')?.trim();
+    if list.is_empty() {
+        return Some(Vec::new());
+    }
+    list.split(',')
+        .map(|number| {
+            let number = number.trim();
+            match number.strip_prefix("0x") {
+                Some(hex) => i64::from_str_radix(hex, 16).ok(),
+                None => number.parse().ok(),
+            }
+        })
+        .collect()
+}
+
+fn escape(string: &str) -> String {
+    string.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Returns a string with its quotes removed and escapes resolved.
+fn unquote(value: &str) -> String {
+    let inner = match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+        Some(inner) => inner,
+        None => return value.to_string(),
+    };
+    let mut string = String::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => string.extend(chars.next()),
+            c => string.push(c),
+        }
+    }
+    string
+}
@@ -1,5 +1,28 @@
+//! Log messages of libevdev.
+//!
+//! By default libevdev prints its messages to stderr. They can be routed to
+//! the `log` crate, or to any closure, globally with `set_log_handler` or for
+//! a single device with `Device::set_log_handler`.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use evdev_rs::logging::{self, LogPriority};
+//!
+//! logging::set_log_priority(LogPriority::Debug);
+//! logging::set_log_handler(logging::forward_to_log);
+//! ```
+
+use libc::{c_char, c_int, c_void};
+use log::{self, Level, Record};
+use std::ffi::CStr;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::sync::{Arc, Mutex};
+
 use raw;
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum LogPriority {
     /// critical errors and application bugs
     Error = raw::LIBEVDEV_LOG_ERROR as isize,
@@ -9,6 +32,51 @@ pub enum LogPriority {
     Debug = raw::LIBEVDEV_LOG_DEBUG as isize,
 }
 
+impl LogPriority {
+    fn from_raw(priority: raw::libevdev_log_priority) -> LogPriority {
+        match priority {
+            raw::LIBEVDEV_LOG_ERROR => LogPriority::Error,
+            raw::LIBEVDEV_LOG_INFO => LogPriority::Info,
+            _ => LogPriority::Debug,
+        }
+    }
+}
+
+impl From<LogPriority> for Level {
+    fn from(priority: LogPriority) -> Level {
+        match priority {
+            LogPriority::Error => Level::Error,
+            LogPriority::Info => Level::Info,
+            LogPriority::Debug => Level::Debug,
+        }
+    }
+}
+
+/// A log message of libevdev.
+#[derive(Clone, Copy, Debug)]
+pub struct LogRecord<'a> {
+    pub priority: LogPriority,
+    /// The libevdev source file which logged the message.
+    pub file: &'a str,
+    pub line: u32,
+    /// The libevdev function which logged the message.
+    pub function: &'a str,
+    /// The formatted message, without the trailing newline.
+    pub message: &'a str,
+}
+
+/// A handler of the global libevdev log messages.
+pub type LogHandler = Arc<dyn Fn(&LogRecord) + Send + Sync>;
+
+/// A handler of the log messages of a single device.
+pub type DeviceLogHandler = Box<dyn Fn(&LogRecord)>;
+
+static GLOBAL_HANDLER: Mutex<Option<LogHandler>> = Mutex::new(None);
+
+static mut GLOBAL_LOGGER: raw::evdev_sys_logger = raw::evdev_sys_logger {
+    handler: global_log,
+    data: ptr::null_mut(),
+};
 
 /// Define the minimum level to be printed to the log handler.
 /// Messages higher than this level are printed, others are discarded. This
@@ -32,3 +100,129 @@ pub fn get_log_priority() -> LogPriority {
         }
     }
 }
+
+/// Install the handler of the libevdev log messages, replacing the default
+/// one printing to stderr. This is a global setting; devices with their own
+/// handler do not use it.
+pub fn set_log_handler<F>(handler: F)
+    where F: Fn(&LogRecord) + Send + Sync + 'static
+{
+    let mut global = GLOBAL_HANDLER.lock().unwrap_or_else(|e| e.into_inner());
+    *global = Some(Arc::new(handler));
+    unsafe {
+        raw::evdev_sys_set_log_function(ptr::addr_of_mut!(GLOBAL_LOGGER));
+    }
+}
+
+/// Remove the global handler. libevdev then discards its log messages.
+pub fn clear_log_handler() {
+    let mut global = GLOBAL_HANDLER.lock().unwrap_or_else(|e| e.into_inner());
+    unsafe {
+        raw::evdev_sys_set_log_function(ptr::null_mut());
+    }
+    *global = None;
+}
+
+/// Forward a libevdev log message to the `log` crate, with the `libevdev`
+/// target and the file and line of libevdev which logged it.
+pub fn forward_to_log(record: &LogRecord) {
+    let level = Level::from(record.priority);
+    let metadata = log::Metadata::builder()
+        .level(level)
+        .target("libevdev")
+        .build();
+    let logger = log::logger();
+    if level > log::max_level() || !logger.enabled(&metadata) {
+        return;
+    }
+
+    logger.log(&Record::builder()
+        .metadata(metadata)
+        .file(Some(record.file))
+        .line(Some(record.line))
+        .args(format_args!("{}: {}", record.function, record.message))
+        .build());
+}
+
+/// Build the record of a message of the C trampolines and pass it on.
+unsafe fn dispatch<F: FnOnce(&LogRecord)>(priority: raw::libevdev_log_priority,
+                                          file: *const c_char, line: c_int,
+                                          function: *const c_char,
+                                          message: *const c_char,
+                                          handler: F) {
+    let string = |s: *const c_char| {
+        if s.is_null() {
+            Default::default()
+        } else {
+            CStr::from_ptr(s).to_string_lossy()
+        }
+    };
+    let file = string(file);
+    let function = string(function);
+    let message = string(message);
+
+    let record = LogRecord {
+        priority: LogPriority::from_raw(priority),
+        file: &file,
+        line: line as u32,
+        function: &function,
+        message: message.trim_end_matches('\n'),
+    };
+    // Unwinding into libevdev is undefined behavior, the panic is dropped.
+    let _ = panic::catch_unwind(AssertUnwindSafe(|| handler(&record)));
+}
+
+extern "C" fn global_log(_dev: *const raw::libevdev,
+                         priority: raw::libevdev_log_priority,
+                         _data: *mut c_void,
+                         file: *const c_char, line: c_int,
+                         function: *const c_char,
+                         message: *const c_char) {
+    unsafe {
+        dispatch(priority, file, line, function, message, |record| {
+            // Called without the lock held, the handler may log or replace
+            // itself.
+            let handler = GLOBAL_HANDLER.lock().unwrap_or_else(|e| e.into_inner()).clone();
+            if let Some(handler) = handler {
+                handler(record);
+            }
+        });
+    }
+}
+
+/// The handler of a device, kept boxed so that the address given to libevdev
+/// stays valid.
+pub(crate) struct DeviceLogger {
+    logger: raw::evdev_sys_logger,
+    handler: DeviceLogHandler,
+}
+
+impl DeviceLogger {
+    pub(crate) fn new(handler: DeviceLogHandler) -> Box<DeviceLogger> {
+        let mut logger = Box::new(DeviceLogger {
+            logger: raw::evdev_sys_logger {
+                handler: device_log,
+                data: ptr::null_mut(),
+            },
+            handler,
+        });
+        logger.logger.data = &logger.handler as *const DeviceLogHandler as *mut c_void;
+        logger
+    }
+
+    pub(crate) fn as_raw(&mut self) -> *mut raw::evdev_sys_logger {
+        &mut self.logger
+    }
+}
+
+extern "C" fn device_log(_dev: *const raw::libevdev,
+                         priority: raw::libevdev_log_priority,
+                         data: *mut c_void,
+                         file: *const c_char, line: c_int,
+                         function: *const c_char,
+                         message: *const c_char) {
+    unsafe {
+        let handler = &*(data as *const DeviceLogHandler);
+        dispatch(priority, file, line, function, message, handler);
+    }
+}
@@ -51,15 +51,20 @@ macro_rules! abs_getter {
     ( $( $func_name:ident, $c_func: ident ),* ) => {
         $(
             pub fn $func_name (&self,
-                               code: u32) -> Result<i32, nix::errno::Errno> {
-                let result = unsafe {
-                    raw::$c_func(self.raw, code as c_uint) as i32
+                               code: u32) -> Result<i32, Error> {
+                // libevdev returns 0 for a missing axis too.
+                let has_axis = unsafe {
+                    raw::libevdev_has_event_code(self.raw, EventType::EV_ABS as c_uint,
+                                                 code as c_uint) != 0
                 };
-
-                match result {
-                    0 => Err(nix::errno::from_i32(0)),
-                    k => Ok(k)
+                if !has_axis {
+                    return Err(Error::UnsupportedCapability(
+                        int_to_event_code(EventType::EV_ABS as u32, code).to_string()));
                 }
+
+                Ok(unsafe {
+                    raw::$c_func(self.raw, code as c_uint) as i32
+                })
             }
         )*
     };
@@ -0,0 +1,295 @@
+//! Hotplug notifications for evdev devices.
+//!
+//! A `Monitor` watches `/dev/input` with inotify and, optionally, listens to
+//! the kernel's `NETLINK_KOBJECT_UEVENT` broadcasts. Devices already present
+//! when the monitor is created are not reported, use `enumerate` for those.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use evdev_rs::{Monitor, MonitorEvent};
+//!
+//! let monitor = Monitor::new().unwrap().with_uevents().unwrap();
+//!
+//! for event in monitor {
+//!     match event.unwrap() {
+//!         MonitorEvent::Added(path, device) =>
+//!             println!("added {}: {}", path.display(), device.name().unwrap_or("")),
+//!         MonitorEvent::Removed(path) => println!("removed {}", path.display()),
+//!     }
+//! }
+//! ```
+
+use device::Device;
+use enumerate::{event_node_number, DEV_INPUT_PATH};
+use error::Error;
+use libc;
+use nix::errno::Errno;
+use nix::poll::{poll, PollFd, PollFlags};
+use nix::sys::inotify::{AddWatchFlags, InitFlags, Inotify};
+use nix::sys::socket::{bind, recv, MsgFlags, NetlinkAddr, SockAddr};
+use nix::unistd::close;
+use std::collections::{HashSet, VecDeque};
+use std::ffi::OsStr;
+use std::fs::File;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+
+/// The multicast group the kernel sends its uevents to.
+const UEVENT_KERNEL_GROUP: u32 = 1;
+
+/// A change in the set of evdev devices.
+pub enum MonitorEvent {
+    /// A device node appeared and could be opened.
+    Added(PathBuf, Device),
+    /// A device node previously reported as `Added` went away.
+    Removed(PathBuf),
+}
+
+/// Watches a directory of evdev nodes for devices being added and removed.
+pub struct Monitor {
+    path: PathBuf,
+    inotify: Inotify,
+    uevents: Option<File>,
+    /// Nodes that exist but could not be opened yet, usually because udev
+    /// has not applied the permissions yet.
+    pending: HashSet<PathBuf>,
+    /// Nodes reported as `Added` and not yet `Removed`.
+    known: HashSet<PathBuf>,
+    queue: VecDeque<MonitorEvent>,
+}
+
+impl Monitor {
+    /// Create a monitor watching `/dev/input` with inotify.
+    pub fn new() -> Result<Monitor, Error> {
+        Monitor::with_path(DEV_INPUT_PATH)
+    }
+
+    /// Create a monitor watching the `event*` nodes in the given directory.
+    pub fn with_path<P: AsRef<Path>>(path: P) -> Result<Monitor, Error> {
+        let inotify = Inotify::init(InitFlags::IN_CLOEXEC | InitFlags::IN_NONBLOCK)
+            .map_err(|e| Error::nix("inotify_init1", e))?;
+        let flags = AddWatchFlags::IN_CREATE | AddWatchFlags::IN_DELETE
+                  | AddWatchFlags::IN_ATTRIB | AddWatchFlags::IN_MOVED_TO
+                  | AddWatchFlags::IN_MOVED_FROM;
+
+        if let Err(error) = inotify.add_watch(path.as_ref(), flags) {
+            let _ = close(inotify.as_raw_fd());
+            return Err(Error::nix("inotify_add_watch", error).with_path(Some(path.as_ref())));
+        }
+
+        Ok(Monitor {
+            path: path.as_ref().to_path_buf(),
+            inotify,
+            uevents: None,
+            pending: HashSet::new(),
+            known: HashSet::new(),
+            queue: VecDeque::new(),
+        })
+    }
+
+    /// Additionally listen to the kernel uevents of the input subsystem.
+    ///
+    /// This opens a `NETLINK_KOBJECT_UEVENT` socket subscribed to the
+    /// kernel's broadcast group.
+    pub fn with_uevents(self) -> Result<Monitor, Error> {
+        let fd = unsafe {
+            libc::socket(libc::AF_NETLINK,
+                         libc::SOCK_DGRAM | libc::SOCK_CLOEXEC | libc::SOCK_NONBLOCK,
+                         libc::NETLINK_KOBJECT_UEVENT)
+        };
+        if fd < 0 {
+            return Err(Error::os("socket", Errno::last()));
+        }
+
+        let socket = unsafe { File::from_raw_fd(fd) };
+        let addr = SockAddr::Netlink(NetlinkAddr::new(0, UEVENT_KERNEL_GROUP));
+        bind(socket.as_raw_fd(), &addr).map_err(|e| Error::nix("bind", e))?;
+
+        Ok(self.with_uevent_source(socket))
+    }
+
+    /// Additionally read uevents from the given datagram socket.
+    ///
+    /// Every datagram must be formatted like the kernel's uevent messages,
+    /// i.e. `action@devpath` followed by `KEY=value` pairs, all separated by
+    /// NUL bytes. This is mostly useful to feed fake uevents through one end
+    /// of a socketpair.
+    pub fn with_uevent_source<S: IntoRawFd>(mut self, source: S) -> Monitor {
+        self.uevents = Some(unsafe { File::from_raw_fd(source.into_raw_fd()) });
+        self
+    }
+
+    /// Wait for the next device to be added or removed.
+    ///
+    /// With a `timeout` of `None` this blocks until an event is available,
+    /// otherwise `Error::WouldBlock` is returned once the timeout expires.
+    pub fn next_event(&mut self, timeout: Option<Duration>)
+                      -> Result<MonitorEvent, Error> {
+        let give_up = timeout.map(|timeout| Instant::now() + timeout);
+
+        loop {
+            if let Some(event) = self.queue.pop_front() {
+                return Ok(event);
+            }
+
+            // Wake-ups which queue nothing must not extend the wait.
+            let now = Instant::now();
+            if give_up.is_some_and(|give_up| give_up <= now) {
+                return Err(Error::WouldBlock);
+            }
+
+            let mut fds = vec![PollFd::new(self.inotify.as_raw_fd(), PollFlags::POLLIN)];
+            if let Some(ref uevents) = self.uevents {
+                fds.push(PollFd::new(uevents.as_raw_fd(), PollFlags::POLLIN));
+            }
+
+            // Round up so that we don't wake up right before the deadline.
+            let timeout_ms = give_up.map_or(-1, |give_up| {
+                let ms = (give_up - now).as_micros().div_ceil(1000);
+                ms.min(libc::c_int::MAX as u128) as libc::c_int
+            });
+            if poll(&mut fds, timeout_ms).map_err(|e| Error::nix("poll", e))? == 0 {
+                return Err(Error::WouldBlock);
+            }
+
+            self.read_inotify()?;
+            if self.uevents.is_some() {
+                self.read_uevents()?;
+            }
+        }
+    }
+
+    fn read_inotify(&mut self) -> Result<(), Error> {
+        let events = match self.inotify.read_events() {
+            Ok(events) => events,
+            Err(nix::Error::Sys(Errno::EAGAIN)) => return Ok(()),
+            Err(error) => return Err(Error::nix("read", error).with_path(Some(&self.path))),
+        };
+
+        for event in events {
+            let name = match event.name {
+                Some(ref name) if event_node_number(name).is_some() => name,
+                _ => continue,
+            };
+            let path = self.path.join(name);
+
+            if event.mask.intersects(AddWatchFlags::IN_DELETE | AddWatchFlags::IN_MOVED_FROM) {
+                self.remove(path);
+            } else if event.mask.intersects(AddWatchFlags::IN_CREATE | AddWatchFlags::IN_MOVED_TO) {
+                self.add(path);
+            } else if self.pending.contains(&path) {
+                // IN_ATTRIB: udev may just have granted us access.
+                self.add(path);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read_uevents(&mut self) -> Result<(), Error> {
+        let mut buf = [0u8; 8192];
+        loop {
+            let fd = match self.uevents {
+                Some(ref uevents) => uevents.as_raw_fd(),
+                None => return Ok(()),
+            };
+            let len = match recv(fd, &mut buf, MsgFlags::MSG_DONTWAIT) {
+                Ok(len) => len,
+                Err(nix::Error::Sys(Errno::EAGAIN)) => return Ok(()),
+                Err(error) => return Err(Error::nix("recv", error)),
+            };
+            if len == 0 {
+                // The other end of the source went away.
+                self.uevents = None;
+                return Ok(());
+            }
+
+            if let Some((action, name)) = parse_uevent(&buf[..len]) {
+                let path = self.path.join(name);
+                match action {
+                    "add" => self.add(path),
+                    "remove" => self.remove(path),
+                    _ => (),
+                }
+            }
+        }
+    }
+
+    fn add(&mut self, path: PathBuf) {
+        if self.known.contains(&path) {
+            return;
+        }
+
+        let device = File::open(&path)
+            .map_err(|e| Error::io("open", e).with_path(Some(&path)))
+            .and_then(Device::new_from_fd);
+
+        match device {
+            Ok(device) => {
+                self.pending.remove(&path);
+                self.known.insert(path.clone());
+                self.queue.push_back(MonitorEvent::Added(path, device));
+            },
+            Err(Error::Os { errno: Errno::EACCES, .. })
+            | Err(Error::Os { errno: Errno::EPERM, .. })
+            | Err(Error::Os { errno: Errno::ENOENT, .. }) => {
+                self.pending.insert(path);
+            },
+            Err(error) => {
+                debug!("ignoring {}: {}", path.display(), error);
+                self.pending.remove(&path);
+            },
+        }
+    }
+
+    fn remove(&mut self, path: PathBuf) {
+        self.pending.remove(&path);
+        if self.known.remove(&path) {
+            self.queue.push_back(MonitorEvent::Removed(path));
+        }
+    }
+}
+
+impl Iterator for Monitor {
+    type Item = Result<MonitorEvent, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.next_event(None))
+    }
+}
+
+impl Drop for Monitor {
+    fn drop(&mut self) {
+        let _ = close(self.inotify.as_raw_fd());
+    }
+}
+
+/// Returns the action and the node name of an input subsystem uevent with a
+/// `DEVNAME` that is an evdev node.
+fn parse_uevent(msg: &[u8]) -> Option<(&str, &OsStr)> {
+    let mut fields = msg.split(|&b| b == 0);
+    let header = ::std::str::from_utf8(fields.next()?).ok()?;
+    let action = &header[..header.find('@')?];
+
+    let mut subsystem = None;
+    let mut devname = None;
+    for field in fields {
+        if let Some(value) = field.strip_prefix(b"SUBSYSTEM=") {
+            subsystem = Some(value);
+        } else if let Some(value) = field.strip_prefix(b"DEVNAME=") {
+            devname = Some(value);
+        }
+    }
+
+    if subsystem != Some(b"input") {
+        return None;
+    }
+
+    let devname = devname?;
+    let name = OsStr::from_bytes(devname.rsplit(|&b| b == b'/').next()?);
+    event_node_number(name).map(|_| (action, name))
+}
@@ -0,0 +1,444 @@
+//! Tracking of multitouch contacts.
+//!
+//! `MtTracker` keeps the state of every slot of a device and turns the
+//! `ABS_MT_*` events of each `SYN_REPORT` frame into touch down, motion and
+//! up records.
+//!
+//! After a `SYN_DROPPED`, feed the tracker the events libevdev returns with
+//! `ReadStatus::Sync`: they bring the slots up to date and the touches that
+//! started or ended meanwhile are reported at the `SYN_REPORT` ending the
+//! delta. If the delta is not read, call `MtTracker::resync` instead.
+//!
+//! Devices using the older protocol A, which have no slots, are handled by
+//! converting their events with `ProtocolAConverter` first.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use evdev_rs::{Device, ReadFlag};
+//! use evdev_rs::mt::{MtTracker, TouchEvent};
+//! use std::fs::File;
+//!
+//! let d = Device::new_from_fd(File::open("/dev/input/event0").unwrap()).unwrap();
+//! let mut tracker = MtTracker::new(&d).unwrap();
+//!
+//! loop {
+//!     let (_, ev) = d.next_event(ReadFlag::NORMAL | ReadFlag::BLOCKING).unwrap();
+//!     for touch in tracker.process(&ev) {
+//!         match touch {
+//!             TouchEvent::TouchDown(t) => println!("down {} at {},{}", t.tracking_id, t.x, t.y),
+//!             TouchEvent::TouchMotion(t) => println!("motion {} to {},{}", t.tracking_id, t.x, t.y),
+//!             TouchEvent::TouchUp(t) => println!("up {}", t.tracking_id),
+//!         }
+//!     }
+//! }
+//! ```
+
+use device::{Device, EnableData};
+use error::Error;
+use {AbsInfo, InputEvent, TimeVal};
+
+use enums::*;
+
+/// The state of a slot, i.e. of the contact it holds.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Touch {
+    /// Index of the slot holding the contact.
+    pub slot: usize,
+    /// Identifier of the contact, -1 if the slot is unused.
+    pub tracking_id: i32,
+    pub x: i32,
+    pub y: i32,
+    pub pressure: i32,
+    pub touch_major: i32,
+    pub touch_minor: i32,
+    pub orientation: i32,
+    /// One of the `MT_TOOL_*` values.
+    pub tool_type: i32,
+}
+
+impl Touch {
+    /// Returns `true` if the slot holds a contact.
+    pub fn is_active(&self) -> bool {
+        self.tracking_id >= 0
+    }
+
+    fn unused(slot: usize) -> Touch {
+        Touch {
+            slot,
+            tracking_id: -1,
+            ..Touch::default()
+        }
+    }
+}
+
+/// A change to a contact, reported at the end of a frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TouchEvent {
+    /// A new contact.
+    TouchDown(Touch),
+    /// A contact changed position, pressure, size or tool.
+    TouchMotion(Touch),
+    /// A contact was lifted, with its last known state.
+    TouchUp(Touch),
+}
+
+/// Keeps the per-slot state of a multitouch device.
+pub struct MtTracker {
+    slots: Vec<Touch>,
+    /// The slots as of the last `SYN_REPORT`.
+    reported: Vec<Touch>,
+    current_slot: usize,
+}
+
+impl MtTracker {
+    /// Create a tracker for the given device, initialized with the slot state
+    /// known to libevdev.
+    ///
+    /// Returns `None` if the device does not support `ABS_MT_SLOT`. The
+    /// contacts present at creation are reported as `TouchDown` at the end of
+    /// the first frame.
+    pub fn new(device: &Device) -> Option<MtTracker> {
+        let num_slots = device.num_slots()?;
+        let mut tracker = MtTracker::with_slots(num_slots.max(0) as usize);
+        tracker.load(device);
+        Some(tracker)
+    }
+
+    /// Create a tracker for a device with `num_slots` slots, all unused.
+    pub fn with_slots(num_slots: usize) -> MtTracker {
+        MtTracker {
+            slots: (0..num_slots).map(Touch::unused).collect(),
+            reported: (0..num_slots).map(Touch::unused).collect(),
+            current_slot: 0,
+        }
+    }
+
+    /// The current state of every slot, including changes from the frame in
+    /// progress.
+    pub fn slots(&self) -> &[Touch] {
+        &self.slots
+    }
+
+    /// The contacts as of the last `SYN_REPORT`.
+    pub fn touches(&self) -> impl Iterator<Item = &Touch> {
+        self.reported.iter().filter(|touch| touch.is_active())
+    }
+
+    /// Process an event. Returns the touch changes of the frame if the event
+    /// is a `SYN_REPORT`, and nothing otherwise.
+    pub fn process(&mut self, event: &InputEvent) -> Vec<TouchEvent> {
+        let code = match event.event_code {
+            EventCode::EV_SYN(EV_SYN::SYN_REPORT) => return self.report(),
+            EventCode::EV_ABS(ref code) => code,
+            _ => return Vec::new(),
+        };
+
+        if *code == EV_ABS::ABS_MT_SLOT {
+            self.current_slot = event.value.max(0) as usize;
+            return Vec::new();
+        }
+
+        let touch = match self.slots.get_mut(self.current_slot) {
+            Some(touch) => touch,
+            None => return Vec::new(),
+        };
+        match *code {
+            EV_ABS::ABS_MT_TRACKING_ID => touch.tracking_id = event.value,
+            EV_ABS::ABS_MT_POSITION_X => touch.x = event.value,
+            EV_ABS::ABS_MT_POSITION_Y => touch.y = event.value,
+            EV_ABS::ABS_MT_PRESSURE => touch.pressure = event.value,
+            EV_ABS::ABS_MT_TOUCH_MAJOR => touch.touch_major = event.value,
+            EV_ABS::ABS_MT_TOUCH_MINOR => touch.touch_minor = event.value,
+            EV_ABS::ABS_MT_ORIENTATION => touch.orientation = event.value,
+            EV_ABS::ABS_MT_TOOL_TYPE => touch.tool_type = event.value,
+            _ => (),
+        }
+
+        Vec::new()
+    }
+
+    /// Reload the slots from the state known to libevdev and report the
+    /// changes since the last frame.
+    ///
+    /// This is meant for callers that let libevdev drop the delta after a
+    /// `SYN_DROPPED` instead of reading it.
+    pub fn resync(&mut self, device: &Device) -> Vec<TouchEvent> {
+        self.load(device);
+        self.report()
+    }
+
+    fn load(&mut self, device: &Device) {
+        let value = |slot: usize, code: EV_ABS| {
+            device.slot_value(slot as u32, &EventCode::EV_ABS(code)).unwrap_or(0)
+        };
+
+        for (slot, touch) in self.slots.iter_mut().enumerate() {
+            *touch = Touch {
+                slot,
+                tracking_id: device.slot_value(slot as u32,
+                                               &EventCode::EV_ABS(EV_ABS::ABS_MT_TRACKING_ID))
+                                   .unwrap_or(-1),
+                x: value(slot, EV_ABS::ABS_MT_POSITION_X),
+                y: value(slot, EV_ABS::ABS_MT_POSITION_Y),
+                pressure: value(slot, EV_ABS::ABS_MT_PRESSURE),
+                touch_major: value(slot, EV_ABS::ABS_MT_TOUCH_MAJOR),
+                touch_minor: value(slot, EV_ABS::ABS_MT_TOUCH_MINOR),
+                orientation: value(slot, EV_ABS::ABS_MT_ORIENTATION),
+                tool_type: value(slot, EV_ABS::ABS_MT_TOOL_TYPE),
+            };
+        }
+        self.current_slot = device.current_slot().unwrap_or(0).max(0) as usize;
+    }
+
+    fn report(&mut self) -> Vec<TouchEvent> {
+        let mut events = Vec::new();
+
+        for (old, new) in self.reported.iter().zip(self.slots.iter()) {
+            if old.is_active() && old.tracking_id != new.tracking_id {
+                events.push(TouchEvent::TouchUp(*old));
+            }
+            if new.is_active() {
+                if old.tracking_id != new.tracking_id {
+                    events.push(TouchEvent::TouchDown(*new));
+                } else if old != new {
+                    events.push(TouchEvent::TouchMotion(*new));
+                }
+            }
+        }
+
+        self.reported.copy_from_slice(&self.slots);
+        events
+    }
+}
+
+/// A contact reported by a protocol A device between two `SYN_MT_REPORT`s.
+#[derive(Clone, Debug, Default)]
+struct Contact {
+    /// The tracking id reported by the device, if any.
+    device_id: Option<i32>,
+    values: Vec<(EV_ABS, i32)>,
+}
+
+impl Contact {
+    fn value(&self, code: &EV_ABS) -> Option<i32> {
+        self.values.iter().find(|&(c, _)| c == code).map(|&(_, value)| value)
+    }
+
+    fn set(&mut self, code: EV_ABS, value: i32) {
+        match self.values.iter_mut().find(|(c, _)| *c == code) {
+            Some(entry) => entry.1 = value,
+            None => self.values.push((code, value)),
+        }
+    }
+
+    /// Squared distance to another contact, by position.
+    fn distance(&self, other: &Contact) -> i64 {
+        let delta = |code| {
+            (self.value(&code).unwrap_or(0) as i64) - (other.value(&code).unwrap_or(0) as i64)
+        };
+        let (dx, dy) = (delta(EV_ABS::ABS_MT_POSITION_X), delta(EV_ABS::ABS_MT_POSITION_Y));
+        dx * dx + dy * dy
+    }
+}
+
+/// The highest tracking id assigned before wrapping around, as in the kernel.
+const TRACKING_ID_MAX: i32 = 0xffff;
+
+/// Converts the events of a protocol A multitouch device to protocol B.
+///
+/// Protocol A devices report all their contacts in every frame, each one
+/// terminated by a `SYN_MT_REPORT`, without identifying them. The converter
+/// matches each contact to the nearest one of the previous frame, or to the
+/// one with the same `ABS_MT_TRACKING_ID` if the device reports it, and
+/// assigns slots and tracking ids. The output is the event sequence a slotted
+/// device would send and can be fed to an `MtTracker` or written to a
+/// `UInputDevice`.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use evdev_rs::{Device, ReadFlag, UInputDevice};
+/// use evdev_rs::mt::ProtocolAConverter;
+/// use std::fs::File;
+///
+/// let d = Device::new_from_fd(File::open("/dev/input/event0").unwrap()).unwrap();
+/// let mut converter = ProtocolAConverter::new(10);
+/// converter.enable_slots(&d).unwrap();
+/// let uinput = UInputDevice::create_from_device(&d).unwrap();
+///
+/// loop {
+///     let (_, ev) = d.next_event(ReadFlag::NORMAL | ReadFlag::BLOCKING).unwrap();
+///     for ev in converter.process(&ev) {
+///         uinput.write_event(&ev).unwrap();
+///     }
+/// }
+/// ```
+pub struct ProtocolAConverter {
+    /// The contact of each slot as of the last frame.
+    slots: Vec<Option<(i32, Contact)>>,
+    /// The slot selected by the last `ABS_MT_SLOT` emitted.
+    output_slot: Option<usize>,
+    next_tracking_id: i32,
+    contacts: Vec<Contact>,
+    contact: Contact,
+    /// The events of the frame which are not about contacts.
+    others: Vec<InputEvent>,
+}
+
+impl ProtocolAConverter {
+    /// Create a converter tracking up to `num_slots` contacts. Extra contacts
+    /// are ignored.
+    pub fn new(num_slots: usize) -> ProtocolAConverter {
+        ProtocolAConverter {
+            slots: vec![None; num_slots],
+            output_slot: None,
+            next_tracking_id: 0,
+            contacts: Vec::new(),
+            contact: Contact::default(),
+            others: Vec::new(),
+        }
+    }
+
+    /// Enable `ABS_MT_SLOT` and `ABS_MT_TRACKING_ID` on the device, with the
+    /// ranges of the converted events.
+    ///
+    /// This is a local modification, meant to turn the device into a template
+    /// for `UInputDevice::create_from_device` republishing the converted
+    /// events.
+    pub fn enable_slots(&self, device: &Device) -> Result<(), Error> {
+        let axis = |maximum| AbsInfo {
+            value: 0,
+            minimum: 0,
+            maximum,
+            fuzz: 0,
+            flat: 0,
+            resolution: 0,
+        };
+
+        device.enable_event_code(&EventCode::EV_ABS(EV_ABS::ABS_MT_SLOT),
+                                 EnableData::Abs(axis(self.slots.len().max(1) as i32 - 1)))?;
+        device.enable_event_code(&EventCode::EV_ABS(EV_ABS::ABS_MT_TRACKING_ID),
+                                 EnableData::Abs(axis(TRACKING_ID_MAX)))
+    }
+
+    /// Process an event of the protocol A device. Returns the converted events
+    /// of the frame if the event is a `SYN_REPORT`, and nothing otherwise.
+    ///
+    /// The converted events carry the timestamp of the `SYN_REPORT`.
+    pub fn process(&mut self, event: &InputEvent) -> Vec<InputEvent> {
+        match event.event_code {
+            EventCode::EV_SYN(EV_SYN::SYN_REPORT) => return self.report(&event.time),
+            EventCode::EV_SYN(EV_SYN::SYN_MT_REPORT) => {
+                let contact = ::std::mem::take(&mut self.contact);
+                if !contact.values.is_empty() {
+                    self.contacts.push(contact);
+                }
+            },
+            // The frame is incomplete, drop it. The next one reports all the
+            // contacts again.
+            EventCode::EV_SYN(EV_SYN::SYN_DROPPED) => {
+                self.contacts.clear();
+                self.contact = Contact::default();
+                self.others.clear();
+            },
+            EventCode::EV_ABS(EV_ABS::ABS_MT_TRACKING_ID) => {
+                self.contact.device_id = Some(event.value);
+            },
+            EventCode::EV_ABS(EV_ABS::ABS_MT_SLOT) => (),
+            EventCode::EV_ABS(ref code) if is_mt_code(code) => {
+                self.contact.set(code.clone(), event.value);
+            },
+            _ => self.others.push(event.clone()),
+        }
+
+        Vec::new()
+    }
+
+    fn report(&mut self, time: &TimeVal) -> Vec<InputEvent> {
+        // Some devices omit the last SYN_MT_REPORT.
+        let contact = ::std::mem::take(&mut self.contact);
+        if !contact.values.is_empty() {
+            self.contacts.push(contact);
+        }
+        let mut contacts: Vec<Option<Contact>> = self.contacts.drain(..).map(Some).collect();
+
+        // Match the contacts with the ones of the last frame, closest first.
+        let mut pairs = Vec::new();
+        for (i, contact) in contacts.iter().enumerate() {
+            let contact = contact.as_ref().unwrap();
+            for (slot, old) in self.slots.iter().enumerate() {
+                let old = match *old {
+                    Some((_, ref old)) => old,
+                    None => continue,
+                };
+                match (contact.device_id, old.device_id) {
+                    (Some(id), Some(old_id)) if id == old_id => pairs.push((0, i, slot)),
+                    (Some(_), Some(_)) => (),
+                    _ => pairs.push((contact.distance(old), i, slot)),
+                }
+            }
+        }
+        pairs.sort();
+
+        let mut assigned: Vec<Option<Contact>> = vec![None; self.slots.len()];
+        for (_, i, slot) in pairs {
+            if assigned[slot].is_none() && contacts[i].is_some() {
+                assigned[slot] = contacts[i].take();
+            }
+        }
+        for contact in contacts.into_iter().flatten() {
+            if let Some(slot) = (0..self.slots.len())
+                .find(|&slot| self.slots[slot].is_none() && assigned[slot].is_none()) {
+                assigned[slot] = Some(contact);
+            }
+        }
+
+        let mut events = Vec::new();
+        for (slot, contact) in assigned.into_iter().enumerate() {
+            let mut slot_events = Vec::new();
+            let state = match (self.slots[slot].take(), contact) {
+                (None, None) => None,
+                (Some(_), None) => {
+                    slot_events.push((EV_ABS::ABS_MT_TRACKING_ID, -1));
+                    None
+                },
+                (None, Some(contact)) => {
+                    let id = self.next_tracking_id;
+                    self.next_tracking_id = (id + 1) & TRACKING_ID_MAX;
+                    slot_events.push((EV_ABS::ABS_MT_TRACKING_ID, id));
+                    slot_events.extend(contact.values.iter().cloned());
+                    Some((id, contact))
+                },
+                (Some((id, old)), Some(contact)) => {
+                    slot_events.extend(contact.values.iter()
+                                       .filter(|&(code, value)| old.value(code) != Some(*value))
+                                       .cloned());
+                    Some((id, contact))
+                },
+            };
+            self.slots[slot] = state;
+
+            if slot_events.is_empty() {
+                continue;
+            }
+            if self.output_slot != Some(slot) {
+                self.output_slot = Some(slot);
+                events.push(InputEvent::new(time, &EventCode::EV_ABS(EV_ABS::ABS_MT_SLOT),
+                                            slot as i32));
+            }
+            for (code, value) in slot_events {
+                events.push(InputEvent::new(time, &EventCode::EV_ABS(code), value));
+            }
+        }
+
+        events.append(&mut self.others);
+        events.push(InputEvent::new(time, &EventCode::EV_SYN(EV_SYN::SYN_REPORT), 0));
+        events
+    }
+}
+
+fn is_mt_code(code: &EV_ABS) -> bool {
+    let code = code.clone() as u32;
+    code >= EV_ABS::ABS_MT_TOUCH_MAJOR as u32 && code <= EV_ABS::ABS_MT_TOOL_Y as u32
+}
@@ -0,0 +1,209 @@
+//! Replaying recorded events through a uinput device.
+//!
+//! A `Player` recreates the recorded device with uinput and writes the
+//! recorded events to it, spaced like they were recorded. It can be driven
+//! with `run`, which blocks until the end of the recording, or with `poll`
+//! from an event loop, which allows pausing and seeking.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use evdev_rs::evemu;
+//! use evdev_rs::player::Player;
+//!
+//! let recording = evemu::load("touchpad.evemu").unwrap();
+//! let mut player = Player::new(&recording).unwrap();
+//! println!("replaying on {}", player.device().devnode().unwrap_or("?"));
+//!
+//! // Give the compositor some time to pick up the new device.
+//! std::thread::sleep(std::time::Duration::from_secs(1));
+//! player.set_speed(2.0);
+//! player.run().unwrap();
+//! ```
+
+use error::Error;
+use recording::Recording;
+use std::thread;
+use std::time::{Duration, Instant};
+use uinput::UInputDevice;
+use {InputEvent, TimeVal};
+
+/// Writes recorded events to a uinput device with the recorded timing.
+pub struct Player {
+    device: UInputDevice,
+    events: Vec<InputEvent>,
+    /// The index of the next event to write.
+    position: usize,
+    speed: f64,
+    looping: bool,
+    paused: bool,
+    /// When the last event was written, and its recorded time. Reset on
+    /// pause and seek so that the next event is written right away.
+    anchor: Option<(Instant, TimeVal)>,
+}
+
+impl Player {
+    /// Create the recorded device with uinput.
+    pub fn new(recording: &Recording) -> Result<Player, Error> {
+        let device = recording.description.to_device()?;
+        let device = UInputDevice::create_from_device(&device)?;
+        Ok(Player::with_device(device, recording.events.clone()))
+    }
+
+    /// Replay events on an existing uinput device.
+    pub fn with_device(device: UInputDevice, events: Vec<InputEvent>) -> Player {
+        Player {
+            device,
+            events,
+            position: 0,
+            speed: 1.0,
+            looping: false,
+            paused: false,
+            anchor: None,
+        }
+    }
+
+    /// Returns the uinput device the events are written to.
+    pub fn device(&self) -> &UInputDevice {
+        &self.device
+    }
+
+    /// Returns the events replayed.
+    pub fn events(&self) -> &[InputEvent] {
+        &self.events
+    }
+
+    /// Returns the index of the next event to write, which is the number of
+    /// events in the recording once it is over.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Returns `true` once all the events have been written.
+    ///
+    /// If looping, `poll` starts over as soon as the last event is written,
+    /// so this is only the case in between, or for the recordings played
+    /// once despite looping.
+    pub fn is_finished(&self) -> bool {
+        self.position >= self.events.len()
+    }
+
+    /// Replay `speed` times as fast as recorded, e.g. 2.0 for twice as fast
+    /// and 0.5 for half as fast. Non-positive speeds are ignored.
+    ///
+    /// The change applies to the wait for the next event too.
+    pub fn set_speed(&mut self, speed: f64) {
+        if speed > 0.0 {
+            self.speed = speed;
+        }
+    }
+
+    /// Start over from the first event once the last one is written.
+    ///
+    /// The first event is then due right after the last one. Recordings with
+    /// all their events at the same time are played once.
+    pub fn set_looping(&mut self, looping: bool) {
+        self.looping = looping;
+    }
+
+    /// Stop writing events until `resume` is called.
+    ///
+    /// The events of a frame partially written are not completed, so the
+    /// device may be left with, e.g., a key down.
+    pub fn pause(&mut self) {
+        self.paused = true;
+        self.anchor = None;
+    }
+
+    /// Resume after `pause`. The next event is written right away.
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Continue from the event at `position`. The event is written right
+    /// away.
+    pub fn seek(&mut self, position: usize) {
+        self.position = position.min(self.events.len());
+        self.anchor = None;
+    }
+
+    /// Continue from the first event recorded at least `offset` after the
+    /// first event of the recording.
+    pub fn seek_time(&mut self, offset: Duration) {
+        let position = match self.events.first() {
+            Some(first) => {
+                let first = first.time.clone();
+                self.events.iter()
+                    .position(|event| delta(&first, &event.time) >= offset)
+                    .unwrap_or(self.events.len())
+            },
+            None => 0,
+        };
+        self.seek(position);
+    }
+
+    /// Write the events due at `now`. Returns when the next event is due,
+    /// or `None` if paused or finished.
+    pub fn poll(&mut self, now: Instant) -> Result<Option<Instant>, Error> {
+        if self.paused {
+            return Ok(None);
+        }
+
+        loop {
+            if self.is_finished() {
+                if !self.looping || self.duration() == Duration::from_secs(0) {
+                    return Ok(None);
+                }
+                // Keep the pace across the wrap-around rather than writing
+                // the first event at `now`.
+                let first = self.events[0].time.clone();
+                self.position = 0;
+                self.anchor = self.anchor.take().map(|(last, _)| (last, first));
+            }
+
+            let event = &self.events[self.position];
+            // Schedule from the due time of the last event rather than from
+            // `now`, so that late polls do not add up.
+            let due = match self.anchor {
+                Some((last, ref base)) => last + delta(base, &event.time).div_f64(self.speed),
+                None => now,
+            };
+            if due > now {
+                return Ok(Some(due));
+            }
+
+            self.device.write_event(event)?;
+            self.anchor = Some((due, event.time.clone()));
+            self.position += 1;
+        }
+    }
+
+    /// The time from the first event to the last one.
+    fn duration(&self) -> Duration {
+        match (self.events.first(), self.events.last()) {
+            (Some(first), Some(last)) => delta(&first.time, &last.time),
+            _ => Duration::from_secs(0),
+        }
+    }
+
+    /// Write all the events, sleeping between them. Returns at the end of
+    /// the recording, or right away if paused. Never returns if looping,
+    /// unless an error occurs or the recording is played once as described
+    /// in `set_looping`.
+    pub fn run(&mut self) -> Result<(), Error> {
+        while let Some(due) = self.poll(Instant::now())? {
+            thread::sleep(due.saturating_duration_since(Instant::now()));
+        }
+        Ok(())
+    }
+}
+
+/// The time elapsed from `from` to `to`, zero if `to` is earlier.
+fn delta(from: &TimeVal, to: &TimeVal) -> Duration {
+    let micros = (to.tv_sec - from.tv_sec) * 1_000_000 + (to.tv_usec - from.tv_usec);
+    Duration::from_micros(micros.max(0) as u64)
+}
@@ -0,0 +1,155 @@
+//! Reading events with transparent `SYN_DROPPED` handling.
+//!
+//! When the kernel buffer of a device overflows, the kernel drops events and
+//! sends a `SYN_DROPPED`. libevdev then computes the events making up the
+//! difference between its view of the device state and the real one, which
+//! the caller must read with `ReadFlag::SYNC` until `Error::WouldBlock`. `Reader` does
+//! this itself and hands the delta over as configured by a `SyncPolicy`.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use evdev_rs::{Device, Reader, ReaderEvent};
+//! use std::fs::File;
+//!
+//! let d = Device::new_from_fd(File::open("/dev/input/event0").unwrap()).unwrap();
+//!
+//! for event in Reader::new(d) {
+//!     match event.unwrap() {
+//!         ReaderEvent::Event(ev) => println!("{:?}", ev),
+//!         ReaderEvent::Delta(ev) => println!("delta {:?}", ev),
+//!         ReaderEvent::Resynced => println!("re-synced"),
+//!     }
+//! }
+//! ```
+
+use device::Device;
+use error::Error;
+use {InputEvent, ReadFlag, ReadStatus};
+
+/// A callback receiving the device and the state delta, see
+/// `SyncPolicy::Callback`.
+pub type SyncCallback = Box<dyn FnMut(&Device, &[InputEvent])>;
+
+/// What a `Reader` does with the device state delta after a `SYN_DROPPED`.
+pub enum SyncPolicy {
+    /// Return the events of the delta as `ReaderEvent::Delta`.
+    Deliver,
+    /// Discard the events of the delta. The state of the device, as returned
+    /// by `Device::event_value` and friends, is still brought up to date.
+    Drop,
+    /// Pass the device and all the events of the delta to a callback.
+    Callback(SyncCallback),
+}
+
+/// An event returned by a `Reader`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ReaderEvent {
+    /// An event sent by the device.
+    Event(InputEvent),
+    /// An event of the state delta following a `SYN_DROPPED`, with
+    /// `SyncPolicy::Deliver`.
+    Delta(InputEvent),
+    /// The device state has been re-synced after a `SYN_DROPPED`. Returned
+    /// after the delta, whatever the policy.
+    Resynced,
+}
+
+/// Reads the events of a device, performing the resync after a `SYN_DROPPED`.
+pub struct Reader {
+    device: Device,
+    policy: SyncPolicy,
+    /// Whether the delta is being returned.
+    syncing: bool,
+}
+
+impl Reader {
+    /// Read from the device, with `SyncPolicy::Deliver`.
+    pub fn new(device: Device) -> Reader {
+        Reader::with_policy(device, SyncPolicy::Deliver)
+    }
+
+    /// Read from the device, with the given policy.
+    pub fn with_policy(device: Device, policy: SyncPolicy) -> Reader {
+        Reader {
+            device,
+            policy,
+            syncing: false,
+        }
+    }
+
+    /// Returns the device this reader reads from.
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    /// Returns the device, dropping the reader.
+    pub fn into_inner(self) -> Device {
+        self.device
+    }
+
+    /// Returns the next event.
+    ///
+    /// `flags` are passed to `Device::next_event` when reading events sent by
+    /// the device. They must not include `ReadFlag::SYNC`, which the reader
+    /// uses on its own.
+    pub fn next_event(&mut self, flags: ReadFlag) -> Result<ReaderEvent, Error> {
+        if self.syncing {
+            return match self.device.next_event(ReadFlag::SYNC) {
+                Ok((_, event)) => Ok(ReaderEvent::Delta(event)),
+                Err(Error::WouldBlock) => {
+                    self.syncing = false;
+                    Ok(ReaderEvent::Resynced)
+                },
+                Err(error) => Err(error),
+            };
+        }
+
+        let (status, event) = self.device.next_event(flags)?;
+        if status == ReadStatus::Success {
+            return Ok(ReaderEvent::Event(event));
+        }
+
+        // The event is the SYN_DROPPED, the delta follows.
+        match self.policy {
+            SyncPolicy::Deliver => {
+                self.syncing = true;
+                self.next_event(flags)
+            },
+            SyncPolicy::Drop => {
+                Reader::read_delta(&self.device)?;
+                Ok(ReaderEvent::Resynced)
+            },
+            SyncPolicy::Callback(ref mut callback) => {
+                let delta = Reader::read_delta(&self.device)?;
+                callback(&self.device, &delta);
+                Ok(ReaderEvent::Resynced)
+            },
+        }
+    }
+
+    fn read_delta(device: &Device) -> Result<Vec<InputEvent>, Error> {
+        let mut delta = Vec::new();
+        loop {
+            match device.next_event(ReadFlag::SYNC) {
+                Ok((_, event)) => delta.push(event),
+                Err(Error::WouldBlock) => return Ok(delta),
+                Err(error) => return Err(error),
+            }
+        }
+    }
+}
+
+impl Iterator for Reader {
+    type Item = Result<ReaderEvent, Error>;
+
+    /// Returns the next event, reading with `ReadFlag::NORMAL |
+    /// ReadFlag::BLOCKING`. Ends when the device's fd is non-blocking and no
+    /// event is available.
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_event(ReadFlag::NORMAL | ReadFlag::BLOCKING) {
+            Err(Error::WouldBlock) => None,
+            result => Some(result),
+        }
+    }
+}
@@ -0,0 +1,190 @@
+//! Snapshots of devices and of their events, as exchanged in bug reports.
+//!
+//! A `DeviceDescription` holds what is needed to recreate a device: its
+//! identity and capabilities. A `Recording` adds the events read from it.
+//! See the `evemu` module to read and write them.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use evdev_rs::{Device, DeviceDescription, UInputDevice};
+//! use std::fs::File;
+//!
+//! let d = Device::new_from_fd(File::open("/dev/input/event0").unwrap()).unwrap();
+//! let description = DeviceDescription::from_device(&d);
+//!
+//! // A virtual copy of the device.
+//! let copy = description.to_device().unwrap();
+//! let uinput = UInputDevice::create_from_device(&copy).unwrap();
+//! ```
+
+use device::{Device, EnableData};
+use error::Error;
+use nix::errno::Errno;
+use {AbsInfo, DeviceId, InputEvent};
+
+use enums::*;
+use util::*;
+
+/// The identity and capabilities of a device.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DeviceDescription {
+    pub name: String,
+    pub phys: Option<String>,
+    pub uniq: Option<String>,
+    pub id: DeviceId,
+    /// The event types supported, `EV_SYN` included.
+    pub types: Vec<EventType>,
+    /// The event codes supported, in ascending order of type and code.
+    ///
+    /// `EV_SYN` codes are implied by the `EV_SYN` type and are not listed.
+    pub codes: Vec<EventCode>,
+    /// The axis information of the `EV_ABS` codes, in ascending order.
+    pub absinfo: Vec<(EV_ABS, AbsInfo)>,
+    pub properties: Vec<InputProp>,
+    /// The state of the LEDs, followed by the state of the switches and by
+    /// the key repeat delay and period.
+    pub state: Vec<(EventCode, i32)>,
+}
+
+impl DeviceDescription {
+    /// Take a snapshot of the device.
+    pub fn from_device(device: &Device) -> DeviceDescription {
+        let mut description = DeviceDescription {
+            name: device.name().unwrap_or("").to_string(),
+            phys: device.phys().map(str::to_string),
+            uniq: device.uniq().map(str::to_string),
+            id: DeviceId {
+                bustype: int_to_bus_type(device.bustype() as u32).unwrap_or(BusType::BUS_VIRTUAL),
+                vendor: device.vendor_id(),
+                product: device.product_id(),
+                version: device.version(),
+            },
+            types: Vec::new(),
+            codes: Vec::new(),
+            absinfo: Vec::new(),
+            properties: Vec::new(),
+            state: Vec::new(),
+        };
+
+        for ev_type in known_types() {
+            if !device.has_event_type(&ev_type) {
+                continue;
+            }
+            description.types.push(ev_type.clone());
+            if ev_type == EventType::EV_SYN {
+                continue;
+            }
+
+            for code in codes_of(&ev_type) {
+                if !device.has_event_code(&code) {
+                    continue;
+                }
+                if let EventCode::EV_ABS(ref abs) = code {
+                    if let Some(absinfo) = device.abs_info(&code) {
+                        description.absinfo.push((abs.clone(), absinfo));
+                    }
+                }
+                description.codes.push(code);
+            }
+        }
+
+        for prop in 0..=InputProp::INPUT_PROP_MAX as u32 {
+            if let Some(prop) = int_to_input_prop(prop) {
+                if device.has_property(&prop) {
+                    description.properties.push(prop);
+                }
+            }
+        }
+
+        for ev_type in &[EventType::EV_LED, EventType::EV_SW, EventType::EV_REP] {
+            for code in description.codes.iter().filter(|code| code_type(code) == *ev_type) {
+                if let Some(value) = device.event_value(code) {
+                    description.state.push((code.clone(), value));
+                }
+            }
+        }
+
+        description
+    }
+
+    /// Create a libevdev device with the described identity and
+    /// capabilities, e.g. to pass to `UInputDevice::create_from_device`.
+    pub fn to_device(&self) -> Result<Device, Error> {
+        let device = Device::new().ok_or_else(|| Error::os("libevdev_new", Errno::ENOMEM))?;
+        device.set_name(&self.name);
+        if let Some(ref phys) = self.phys {
+            device.set_phys(phys);
+        }
+        if let Some(ref uniq) = self.uniq {
+            device.set_uniq(uniq);
+        }
+        device.set_bustype(self.id.bustype.clone() as u16);
+        device.set_vendor_id(self.id.vendor);
+        device.set_product_id(self.id.product);
+        device.set_version(self.id.version);
+
+        for ev_type in &self.types {
+            device.enable_event_type(ev_type)?;
+        }
+        for code in &self.codes {
+            match *code {
+                EventCode::EV_ABS(ref abs) => {
+                    let absinfo = self.absinfo.iter()
+                        .find(|(a, _)| a == abs)
+                        .map(|&(_, absinfo)| absinfo)
+                        .unwrap_or_default();
+                    device.enable_event_code(code, EnableData::Abs(absinfo))?;
+                },
+                EventCode::EV_REP(_) => {
+                    let value = self.state.iter()
+                        .find(|(c, _)| c == code)
+                        .map_or(0, |&(_, value)| value);
+                    device.enable_event_code(code, EnableData::Rep(value))?;
+                },
+                _ => device.enable_event_code(code, EnableData::None)?,
+            }
+        }
+        for prop in &self.properties {
+            device.enable_property(prop)?;
+        }
+        // The repeat values have been set when enabling their codes.
+        let state = self.state.iter().filter(|(code, _)| code_type(code) != EventType::EV_REP);
+        for (code, value) in state {
+            device.set_event_value(code, *value)?;
+        }
+
+        Ok(device)
+    }
+}
+
+/// A device description together with events read from the device.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Recording {
+    pub description: DeviceDescription,
+    /// The events, in the order they were read.
+    pub events: Vec<InputEvent>,
+}
+
+/// The event types known to evdev-rs, in ascending order.
+pub(crate) fn known_types() -> impl Iterator<Item = EventType> {
+    (0..EventType::EV_MAX as u32)
+        .filter_map(int_to_event_type)
+        .filter(|ev_type| *ev_type != EventType::EV_UNK)
+}
+
+/// All the codes of a known event type, `EV_UNK` ones included, in ascending
+/// order.
+pub(crate) fn codes_of(ev_type: &EventType) -> impl Iterator<Item = EventCode> {
+    let number = ev_type.clone() as u32;
+    let max = EventType::get_max(ev_type).unwrap_or(-1);
+    (0..=max).map(move |code| int_to_event_code(number, code as u32))
+}
+
+/// Returns the type of an event code.
+pub(crate) fn code_type(code: &EventCode) -> EventType {
+    let (ev_type, _) = event_code_to_int(code);
+    int_to_event_type(ev_type).unwrap_or(EventType::EV_UNK)
+}
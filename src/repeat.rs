@@ -0,0 +1,110 @@
+//! Software key repeat, for devices where the kernel does not repeat keys.
+//!
+//! `KeyRepeater` follows the key presses of a device and, using the timers of
+//! an `EventLoop`, generates the `EV_KEY` events with value 2 the kernel would
+//! send if it handled the repeat itself.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use evdev_rs::{Device, EventLoop, LoopEvent};
+//! use evdev_rs::repeat::KeyRepeater;
+//! use std::fs::File;
+//! use std::time::Duration;
+//!
+//! let mut event_loop = EventLoop::new().unwrap();
+//! let d = Device::new_from_fd(File::open("/dev/input/event0").unwrap()).unwrap();
+//! event_loop.add(d).unwrap();
+//! let mut repeater = KeyRepeater::new(Duration::from_millis(250), Duration::from_millis(33));
+//!
+//! event_loop.run(|event_loop, event| {
+//!     match event {
+//!         LoopEvent::Event(_, ev) => {
+//!             repeater.process(event_loop, &ev);
+//!             println!("{:?}", ev);
+//!         },
+//!         LoopEvent::Timer(token) => {
+//!             for ev in repeater.timer(token) {
+//!                 println!("{:?}", ev);
+//!             }
+//!         },
+//!         _ => (),
+//!     }
+//!     true
+//! }).unwrap();
+//! ```
+
+use event_loop::{EventLoop, TimerToken};
+use std::convert::TryFrom;
+use std::time::{Duration, SystemTime};
+use {InputEvent, TimeVal};
+
+use enums::*;
+
+/// Generates key repeat events on the timers of an `EventLoop`.
+///
+/// As with the kernel, the last key pressed is repeated until any key is
+/// released.
+pub struct KeyRepeater {
+    delay: Duration,
+    period: Duration,
+    /// The key being repeated and the timer repeating it.
+    current: Option<(EV_KEY, TimerToken)>,
+}
+
+impl KeyRepeater {
+    /// Repeat keys held for `delay`, every `period`.
+    pub fn new(delay: Duration, period: Duration) -> KeyRepeater {
+        KeyRepeater {
+            delay,
+            period,
+            current: None,
+        }
+    }
+
+    /// Follow an event read from the device, starting or stopping the repeat
+    /// timer on key presses and releases.
+    pub fn process(&mut self, event_loop: &mut EventLoop, event: &InputEvent) {
+        let key = match event.event_code {
+            EventCode::EV_KEY(ref key) => key,
+            _ => return,
+        };
+
+        match event.value {
+            0 => self.stop(event_loop),
+            1 => {
+                self.stop(event_loop);
+                let token = event_loop.add_timer(self.delay, Some(self.period));
+                self.current = Some((key.clone(), token));
+            },
+            _ => (),
+        }
+    }
+
+    /// Stop repeating the current key, if any.
+    pub fn stop(&mut self, event_loop: &mut EventLoop) {
+        if let Some((_, token)) = self.current.take() {
+            event_loop.cancel_timer(token);
+        }
+    }
+
+    /// Returns the key being repeated, if any.
+    pub fn key(&self) -> Option<&EV_KEY> {
+        self.current.as_ref().map(|(key, _)| key)
+    }
+
+    /// Handle a timer of the event loop. If it is the repeat timer, returns
+    /// the repeat event followed by a `SYN_REPORT`, and nothing otherwise.
+    pub fn timer(&self, token: TimerToken) -> Vec<InputEvent> {
+        let key = match self.current {
+            Some((ref key, current)) if current == token => key,
+            _ => return Vec::new(),
+        };
+
+        let time = TimeVal::try_from(SystemTime::now()).unwrap_or_else(|_| TimeVal::new(0, 0));
+        vec![
+            InputEvent::new(&time, &EventCode::EV_KEY(key.clone()), 2),
+            InputEvent::new(&time, &EventCode::EV_SYN(EV_SYN::SYN_REPORT), 0),
+        ]
+    }
+}
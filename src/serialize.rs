@@ -0,0 +1,257 @@
+//! `Serialize` and `Deserialize` for the event types and codes, with the
+//! `serde` feature.
+//!
+//! Human-readable formats get the kernel names of event types, codes,
+//! properties and bus types, e.g. `"KEY_A"`. Codes without a name, e.g.
+//! `EventCode::EV_UNK`, are written as a `[type, code]` pair. Other formats
+//! get the numbers. Sets of codes are written as sequences of codes.
+
+use bitset::{BitCode, BitSet};
+use recording::known_types;
+use serde::de::{self, Deserialize, Deserializer, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeSeq, Serializer};
+use std::convert::TryFrom;
+use std::fmt;
+
+use enums::*;
+use util::*;
+
+/// A name or a number, as deserialized.
+enum Value {
+    Name(String),
+    Number(u32),
+    Pair(u32, u32),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Value::Name(ref name) => write!(f, "{:?}", name),
+            Value::Number(number) => write!(f, "{}", number),
+            Value::Pair(ev_type, code) => write!(f, "[{}, {}]", ev_type, code),
+        }
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a name, a number or a [type, code] pair")
+    }
+
+    fn visit_str<E: de::Error>(self, name: &str) -> Result<Value, E> {
+        Ok(Value::Name(name.to_string()))
+    }
+
+    fn visit_u64<E: de::Error>(self, number: u64) -> Result<Value, E> {
+        u32::try_from(number)
+            .map(Value::Number)
+            .map_err(|_| E::invalid_value(de::Unexpected::Unsigned(number), &self))
+    }
+
+    fn visit_i64<E: de::Error>(self, number: i64) -> Result<Value, E> {
+        u32::try_from(number)
+            .map(Value::Number)
+            .map_err(|_| E::invalid_value(de::Unexpected::Signed(number), &self))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let ev_type = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let code = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(1, &self))?;
+        Ok(Value::Pair(ev_type, code))
+    }
+}
+
+/// Deserialize a name or a number from a human-readable format, a number
+/// from others.
+fn deserialize_value<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Value, D::Error> {
+    if deserializer.is_human_readable() {
+        deserializer.deserialize_any(ValueVisitor)
+    } else {
+        u32::deserialize(deserializer).map(Value::Number)
+    }
+}
+
+fn unknown<E: de::Error>(what: &str, value: &Value) -> E {
+    E::custom(format!("unknown {} {}", what, value))
+}
+
+/// Returns `true` if `name` can be passed to the libevdev lookups.
+fn valid_name(name: &str) -> bool {
+    !name.contains('\0')
+}
+
+/// Serialize `name` to human-readable formats, unless empty, `number`
+/// otherwise.
+fn serialize_name<S: Serializer>(serializer: S, name: &str, number: u32) -> Result<S::Ok, S::Error> {
+    if serializer.is_human_readable() && !name.is_empty() {
+        serializer.serialize_str(name)
+    } else {
+        serializer.serialize_u32(number)
+    }
+}
+
+impl Serialize for EventType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_name(serializer, &self.to_string(), self.clone() as u32)
+    }
+}
+
+impl<'de> Deserialize<'de> for EventType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<EventType, D::Error> {
+        let value = deserialize_value(deserializer)?;
+        let ev_type = match value {
+            Value::Name(ref name) if valid_name(name) => EventType::from_str(name),
+            Value::Number(number) => int_to_event_type(number),
+            _ => None,
+        };
+        ev_type.ok_or_else(|| unknown("event type", &value))
+    }
+}
+
+impl Serialize for EventCode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let named = match *self {
+            // Their names are the ones of other types, or there are none.
+            EventCode::EV_UNK { .. } | EventCode::EV_PWR |
+            EventCode::EV_FF_STATUS(_) | EventCode::EV_MAX => false,
+            _ => serializer.is_human_readable(),
+        };
+        let name = if named { self.to_string() } else { String::new() };
+        if name.is_empty() {
+            event_code_to_int(self).serialize(serializer)
+        } else {
+            serializer.serialize_str(&name)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for EventCode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<EventCode, D::Error> {
+        let value = if deserializer.is_human_readable() {
+            deserializer.deserialize_any(ValueVisitor)?
+        } else {
+            let (ev_type, code) = <(u32, u32)>::deserialize(deserializer)?;
+            Value::Pair(ev_type, code)
+        };
+        let code = match value {
+            Value::Name(ref name) if valid_name(name) => {
+                known_types()
+                    .filter(|ev_type| *ev_type != EventType::EV_FF_STATUS)
+                    .find_map(|ev_type| EventCode::from_str(&ev_type, name))
+            },
+            Value::Pair(ev_type, code) if ev_type <= EventType::EV_MAX as u32 => {
+                Some(int_to_event_code(ev_type, code))
+            },
+            _ => None,
+        };
+        code.ok_or_else(|| unknown("event code", &value))
+    }
+}
+
+/// Implement `Serialize` and `Deserialize` for the codes of an event type,
+/// named like the type.
+macro_rules! code_serde {
+    ($($code:ident => $int_to_code:ident,)*) => {
+        $(
+            impl Serialize for $code {
+                fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                    let name = EventCode::$code(self.clone()).to_string();
+                    serialize_name(serializer, &name, self.clone() as u32)
+                }
+            }
+
+            impl<'de> Deserialize<'de> for $code {
+                fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<$code, D::Error> {
+                    let value = deserialize_value(deserializer)?;
+                    let code = match value {
+                        Value::Name(ref name) if valid_name(name) => {
+                            match EventCode::from_str(&EventType::$code, name) {
+                                Some(EventCode::$code(code)) => Some(code),
+                                _ => None,
+                            }
+                        },
+                        Value::Number(number) => $int_to_code(number),
+                        _ => None,
+                    };
+                    code.ok_or_else(|| unknown(stringify!($code), &value))
+                }
+            }
+        )*
+    };
+}
+
+code_serde! {
+    EV_SYN => int_to_ev_syn,
+    EV_KEY => int_to_ev_key,
+    EV_REL => int_to_ev_rel,
+    EV_ABS => int_to_ev_abs,
+    EV_MSC => int_to_ev_msc,
+    EV_SW => int_to_ev_sw,
+    EV_LED => int_to_ev_led,
+    EV_SND => int_to_ev_snd,
+    EV_REP => int_to_ev_rep,
+    EV_FF => int_to_ev_ff,
+}
+
+impl Serialize for InputProp {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_name(serializer, &self.to_string(), self.clone() as u32)
+    }
+}
+
+impl<'de> Deserialize<'de> for InputProp {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<InputProp, D::Error> {
+        let value = deserialize_value(deserializer)?;
+        let prop = match value {
+            Value::Name(ref name) if valid_name(name) => InputProp::from_str(name),
+            Value::Number(number) => int_to_input_prop(number),
+            _ => None,
+        };
+        prop.ok_or_else(|| unknown("input property", &value))
+    }
+}
+
+impl Serialize for BusType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // libevdev has no names for bus types, the variants are named like
+        // the kernel constants.
+        serialize_name(serializer, &format!("{:?}", self), self.clone() as u32)
+    }
+}
+
+impl<'de> Deserialize<'de> for BusType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<BusType, D::Error> {
+        let value = deserialize_value(deserializer)?;
+        let bustype = match value {
+            Value::Name(ref name) => {
+                (0..=u8::MAX as u32)
+                    .filter_map(int_to_bus_type)
+                    .find(|bustype| format!("{:?}", bustype) == *name)
+            },
+            Value::Number(number) => int_to_bus_type(number),
+            _ => None,
+        };
+        bustype.ok_or_else(|| unknown("bus type", &value))
+    }
+}
+
+impl<T: BitCode + Serialize> Serialize for BitSet<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // Not `len`, which counts the codes unknown to evdev-rs too.
+        let mut seq = serializer.serialize_seq(Some(self.iter().count()))?;
+        for code in self {
+            seq.serialize_element(&code)?;
+        }
+        seq.end()
+    }
+}
+
+impl<'de, T: BitCode + Deserialize<'de>> Deserialize<'de> for BitSet<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<BitSet<T>, D::Error> {
+        Vec::<T>::deserialize(deserializer).map(|codes| codes.into_iter().collect())
+    }
+}
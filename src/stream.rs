@@ -0,0 +1,131 @@
+//! Asynchronous reading of events, available with the `tokio` feature.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use evdev_rs::{Device, EventStream};
+//! use futures::StreamExt;
+//! use std::fs::File;
+//!
+//! let device = Device::new_from_fd(File::open("/dev/input/event0")?)?;
+//! let mut events = EventStream::new(device)?;
+//!
+//! while let Some(event) = events.next().await {
+//!     let (status, event) = event?;
+//!     println!("{:?}", event);
+//! }
+//! ```
+
+use device::Device;
+use error::Error;
+use futures_core::Stream;
+use std::os::unix::io::AsRawFd;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::unix::AsyncFd;
+use {InputEvent, ReadFlag, ReadStatus};
+
+use util::*;
+
+/// A `Stream` of the events of a device, driven by the tokio reactor.
+///
+/// The stream yields the same `(ReadStatus, InputEvent)` pairs as
+/// `Device::next_event`. After a `SYN_DROPPED`, the events making up the
+/// device state delta are yielded with `ReadStatus::Sync`, then the stream
+/// carries on with `ReadStatus::Success` events.
+///
+/// Once the device is gone, the stream yields `Error::DeviceGone` once and
+/// then ends.
+pub struct EventStream {
+    device: AsyncFd<Device>,
+    syncing: bool,
+    /// Set once `Error::DeviceGone` has been yielded.
+    done: bool,
+}
+
+impl EventStream {
+    /// Register the device with the tokio reactor.
+    ///
+    /// The device's file descriptor is switched to non-blocking mode. This must
+    /// be called from within a tokio runtime.
+    pub fn new(device: Device) -> Result<EventStream, Error> {
+        set_nonblocking(device.as_raw_fd())?;
+
+        Ok(EventStream {
+            device: AsyncFd::new(device).map_err(|e| Error::io("epoll_ctl", e))?,
+            syncing: false,
+            done: false,
+        })
+    }
+
+    /// Returns the device this stream reads from.
+    pub fn device(&self) -> &Device {
+        self.device.get_ref()
+    }
+
+    /// Deregister the device from the reactor and return it.
+    pub fn into_inner(self) -> Device {
+        self.device.into_inner()
+    }
+
+    /// Read the next event already available to libevdev, either from its
+    /// internal queue or from the fd. Returns `None` once both are empty.
+    fn read_event(device: &Device, syncing: &mut bool)
+                  -> Option<Result<(ReadStatus, InputEvent), Error>> {
+        loop {
+            let flags = if *syncing { ReadFlag::SYNC } else { ReadFlag::NORMAL };
+            match device.next_event(flags) {
+                Ok((ReadStatus::Sync, event)) => {
+                    *syncing = true;
+                    return Some(Ok((ReadStatus::Sync, event)));
+                },
+                Ok(result) => return Some(Ok(result)),
+                // All the events of the delta have been read, go back to
+                // reading normally.
+                Err(Error::WouldBlock) if *syncing => *syncing = false,
+                Err(Error::WouldBlock) => return None,
+                Err(error) => return Some(Err(error)),
+            }
+        }
+    }
+
+    /// Yield `result`, ending the stream after it if the device is gone.
+    fn ready(&mut self, result: Result<(ReadStatus, InputEvent), Error>)
+             -> Poll<Option<Result<(ReadStatus, InputEvent), Error>>> {
+        if let Err(Error::DeviceGone { .. }) = result {
+            self.done = true;
+        }
+        Poll::Ready(Some(result))
+    }
+}
+
+impl Stream for EventStream {
+    type Item = Result<(ReadStatus, InputEvent), Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context)
+                 -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.done {
+            return Poll::Ready(None);
+        }
+
+        // libevdev reads several events off the fd at once, drain its queue
+        // before relying on the fd readiness again.
+        if let Some(result) = EventStream::read_event(this.device.get_ref(), &mut this.syncing) {
+            return this.ready(result);
+        }
+
+        loop {
+            let mut guard = match this.device.poll_read_ready(cx) {
+                Poll::Ready(Ok(guard)) => guard,
+                Poll::Ready(Err(error)) => return Poll::Ready(Some(Err(Error::io("poll", error)))),
+                Poll::Pending => return Poll::Pending,
+            };
+
+            if let Some(result) = EventStream::read_event(guard.get_inner(), &mut this.syncing) {
+                return this.ready(result);
+            }
+            guard.clear_ready();
+        }
+    }
+}
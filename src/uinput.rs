@@ -1,15 +1,58 @@
-use InputEvent;
-use libc::c_int;
+use {AbsInfo, DeviceId, InputEvent, TimeVal};
+use libc::{self, c_int, c_uint, c_ulong};
 use device::Device;
-use std::fs::File;
-use std::os::unix::io::FromRawFd;
+use error::Error;
+use std::ffi::CString;
+use std::fs::{self, File, OpenOptions};
+use std::mem;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::{AsRawFd, RawFd};
 use nix::errno::Errno;
+use nix::poll::{poll, PollFd, PollFlags};
 
+use enums::*;
+use ff::Effect;
+use ioctl;
 use util::*;
 
+/// The path of the uinput character device.
+pub const UINPUT_PATH: &str = "/dev/uinput";
+
+enum Backend {
+    /// A device created by libevdev from a `Device`.
+    Libevdev(*mut raw::libevdev_uinput),
+    /// A device created with `UInputBuilder` through the uinput ioctls.
+    Kernel {
+        file: File,
+        syspath: Option<String>,
+        devnode: Option<String>,
+    },
+}
+
+/// A request made by a client of a uinput device: a force feedback request,
+/// or any other event written to the device.
+#[derive(Clone, Debug, PartialEq)]
+pub enum UInputRequest {
+    /// Upload a new effect, or update an existing one if its id is already
+    /// in use. The id has been assigned by the kernel.
+    Upload(Effect),
+    /// Erase the effect with this id.
+    Erase(i16),
+    /// Play the effect with this id the given number of times, or stop it if
+    /// the count is 0.
+    Play(i16, i32),
+    /// Set the force feedback gain.
+    Gain(u16),
+    /// Set the autocenter strength.
+    Autocenter(u16),
+    /// Any other event written by a client, e.g. an EV_LED event setting
+    /// the state of an LED.
+    Other(InputEvent),
+}
+
 /// Opaque struct representing an evdev uinput device
 pub struct UInputDevice {
-    raw: *mut raw::libevdev_uinput
+    backend: Backend,
 }
 
 impl UInputDevice {
@@ -17,22 +60,29 @@ impl UInputDevice {
     ///
     /// The uinput device will be an exact copy of the libevdev device, minus
     /// the bits that uinput doesn't allow to be set.
-    pub fn create_from_device(device: &Device) -> Result<UInputDevice, Errno> {
+    pub fn create_from_device(device: &Device) -> Result<UInputDevice, Error> {
         let mut libevdev_uinput = 0 as *mut _;
         let result = unsafe {
             raw::libevdev_uinput_create_from_device(device.raw, raw::LIBEVDEV_UINPUT_OPEN_MANAGED, &mut libevdev_uinput)
         };
 
         match result {
-            0 => Ok(UInputDevice { raw: libevdev_uinput }),
-            error => Err(Errno::from_i32(-error))
+            0 => Ok(UInputDevice { backend: Backend::Libevdev(libevdev_uinput) }),
+            error => Err(Error::libevdev("libevdev_uinput_create_from_device", error))
         }
     }
 
     /// Return the device node representing this uinput device.
     ///
     /// This relies on libevdev_uinput_get_syspath() to provide a valid syspath.
-    string_getter!(devnode, libevdev_uinput_get_devnode);
+    pub fn devnode(&self) -> Option<&str> {
+        match self.backend {
+            Backend::Libevdev(raw) => ptr_to_str(unsafe {
+                raw::libevdev_uinput_get_devnode(raw)
+            }),
+            Backend::Kernel { ref devnode, .. } => devnode.as_ref().map(|s| s.as_str()),
+        }
+    }
 
     /// Return the syspath representing this uinput device.
     ///
@@ -42,24 +92,102 @@ impl UInputDevice {
     /// The syspath returned is the one of the input node itself
     /// (e.g. /sys/devices/virtual/input/input123), not the syspath of the
     /// device node returned with libevdev_uinput_get_devnode().
-    string_getter!(syspath, libevdev_uinput_get_syspath);
+    pub fn syspath(&self) -> Option<&str> {
+        match self.backend {
+            Backend::Libevdev(raw) => ptr_to_str(unsafe {
+                raw::libevdev_uinput_get_syspath(raw)
+            }),
+            Backend::Kernel { ref syspath, .. } => syspath.as_ref().map(|s| s.as_str()),
+        }
+    }
 
     /// Return the file descriptor used to create this uinput device.
     ///
     /// This is the fd pointing to /dev/uinput. This file descriptor may be used
-    /// to write events that are emitted by the uinput device. Closing this file
-    ///  descriptor will destroy the uinput device.
-    pub fn fd(&self) -> Option<File> {
-        let result = unsafe {
-            raw::libevdev_uinput_get_fd(self.raw)
-        };
+    /// to write events that are emitted by the uinput device. It remains owned
+    /// by this device: closing it would destroy the uinput device.
+    pub fn fd(&self) -> Option<RawFd> {
+        match self.as_raw_fd() {
+            fd if fd < 0 => None,
+            fd => Some(fd),
+        }
+    }
 
-        if result == 0 {
-            None
-        } else {
-            unsafe {
-                let f = File::from_raw_fd(result);
-                Some(f)
+    /// Serve the requests made to the device by its clients.
+    ///
+    /// This reads the pending requests from the uinput file descriptor, calls
+    /// `handler` for each of them and returns once there are none left. It
+    /// does not block, so it is meant to be called whenever the file
+    /// descriptor becomes readable.
+    ///
+    /// The result of the handler for an `Upload` or `Erase` request is
+    /// reported back to the client, whose `EVIOCSFF`/`EVIOCRMFF` ioctl blocks
+    /// until then. Uploads of effects which cannot be represented by
+    /// `Effect` are rejected with `EINVAL` without calling the handler.
+    ///
+    /// The other events written by clients are read from the same file
+    /// descriptor, they are passed to the handler as `UInputRequest::Other`.
+    ///
+    /// The other requests have no client waiting for their result, so an
+    /// error of the handler for them is returned instead. The requests not
+    /// handled yet are left pending for the next call.
+    pub fn handle_requests<F>(&self, mut handler: F) -> Result<(), Error>
+        where F: FnMut(UInputRequest) -> Result<(), Error>
+    {
+        let fd = self.as_raw_fd();
+        loop {
+            let mut fds = [PollFd::new(fd, PollFlags::POLLIN)];
+            if poll(&mut fds, 0).map_err(|e| Error::nix("poll", e))? == 0 {
+                return Ok(());
+            }
+
+            let mut event: libc::input_event = unsafe { mem::zeroed() };
+            read_struct(fd, &mut event)?;
+
+            match (event.type_, event.code) {
+                (ioctl::EV_UINPUT, ioctl::UI_FF_UPLOAD) => {
+                    let mut upload: libc::uinput_ff_upload = unsafe { mem::zeroed() };
+                    upload.request_id = event.value as u32;
+                    unsafe {
+                        ioctl::ui_begin_ff_upload(fd, &mut upload)
+                            .map_err(|e| Error::nix("UI_BEGIN_FF_UPLOAD", e))?;
+                    }
+                    upload.retval = match Effect::from_raw(&upload.effect) {
+                        Some(effect) => errno_to_retval(handler(UInputRequest::Upload(effect))),
+                        None => -(Errno::EINVAL as i32),
+                    };
+                    unsafe {
+                        ioctl::ui_end_ff_upload(fd, &upload)
+                            .map_err(|e| Error::nix("UI_END_FF_UPLOAD", e))?;
+                    }
+                },
+                (ioctl::EV_UINPUT, ioctl::UI_FF_ERASE) => {
+                    let mut erase: libc::uinput_ff_erase = unsafe { mem::zeroed() };
+                    erase.request_id = event.value as u32;
+                    unsafe {
+                        ioctl::ui_begin_ff_erase(fd, &mut erase)
+                            .map_err(|e| Error::nix("UI_BEGIN_FF_ERASE", e))?;
+                    }
+                    erase.retval = errno_to_retval(handler(UInputRequest::Erase(erase.effect_id as i16)));
+                    unsafe {
+                        ioctl::ui_end_ff_erase(fd, &erase)
+                            .map_err(|e| Error::nix("UI_END_FF_ERASE", e))?;
+                    }
+                },
+                (t, code) if t == EventType::EV_FF as u16 => {
+                    let request = if code == EV_FF::FF_GAIN as u16 {
+                        UInputRequest::Gain(event.value as u16)
+                    } else if code == EV_FF::FF_AUTOCENTER as u16 {
+                        UInputRequest::Autocenter(event.value as u16)
+                    } else {
+                        UInputRequest::Play(code as i16, event.value)
+                    };
+                    handler(request)?;
+                },
+                (t, _) if t <= EventType::EV_MAX as u16 => {
+                    handler(UInputRequest::Other(InputEvent::from_raw(&event)))?;
+                },
+                _ => (),
             }
         }
     }
@@ -69,25 +197,367 @@ impl UInputDevice {
     /// It is the caller's responsibility that any event sequence is terminated
     /// with an EV_SYN/SYN_REPORT/0 event. Otherwise, listeners on the device
     /// node will not see the events until the next EV_SYN event is posted.
-    pub fn write_event(&self, event: &InputEvent) -> Result<(), Errno> {
+    pub fn write_event(&self, event: &InputEvent) -> Result<(), Error> {
         let (ev_type, ev_code) = event_code_to_int(&event.event_code);
         let ev_value = event.value as c_int;
 
-        let result = unsafe {
-            raw::libevdev_uinput_write_event(self.raw, ev_type, ev_code, ev_value)
+        let file = match self.backend {
+            Backend::Libevdev(raw) => {
+                let result = unsafe {
+                    raw::libevdev_uinput_write_event(raw, ev_type, ev_code, ev_value)
+                };
+
+                return match result {
+                    0 => Ok(()),
+                    error => Err(Error::libevdev("libevdev_uinput_write_event", error))
+                };
+            },
+            Backend::Kernel { ref file, .. } => file,
         };
 
-        match result {
-            0 => Ok(()),
-            error => Err(Errno::from_i32(-error))
+        // The kernel sets the timestamp of injected events.
+        let event = InputEvent::new(&TimeVal::new(0, 0), &event.event_code, ev_value).as_raw();
+        write_struct(file, &event)
+    }
+}
+
+impl AsRawFd for UInputDevice {
+    /// Returns the file descriptor pointing to /dev/uinput.
+    fn as_raw_fd(&self) -> RawFd {
+        match self.backend {
+            Backend::Libevdev(raw) => unsafe {
+                raw::libevdev_uinput_get_fd(raw)
+            },
+            Backend::Kernel { ref file, .. } => file.as_raw_fd(),
         }
     }
 }
 
 impl Drop for UInputDevice {
     fn drop(&mut self) {
+        match self.backend {
+            Backend::Libevdev(raw) => unsafe {
+                raw::libevdev_uinput_destroy(raw);
+            },
+            Backend::Kernel { ref file, .. } => unsafe {
+                let _ = ioctl::ui_dev_destroy(file.as_raw_fd());
+            },
+        }
+    }
+}
+
+/// Builds a uinput device from scratch, without going through a `Device`.
+///
+/// The device is set up with `UI_DEV_SETUP` and `UI_ABS_SETUP` and, on
+/// kernels older than 4.5, with the legacy `uinput_user_dev` write.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use evdev_rs::{AbsInfo, UInputBuilder};
+/// use evdev_rs::enums::*;
+///
+/// let absinfo = AbsInfo {
+///     value: 0,
+///     minimum: -32768,
+///     maximum: 32767,
+///     fuzz: 16,
+///     flat: 128,
+///     resolution: 0,
+/// };
+///
+/// let gamepad = UInputBuilder::new()
+///     .name("virtual gamepad")
+///     .code(&EventCode::EV_KEY(EV_KEY::BTN_SOUTH))
+///     .code(&EventCode::EV_KEY(EV_KEY::BTN_EAST))
+///     .abs(&EV_ABS::ABS_X, &absinfo)
+///     .abs(&EV_ABS::ABS_Y, &absinfo)
+///     .ff(&EV_FF::FF_RUMBLE)
+///     .build()
+///     .unwrap();
+/// ```
+pub struct UInputBuilder {
+    name: String,
+    phys: Option<String>,
+    id: libc::input_id,
+    props: Vec<InputProp>,
+    types: Vec<EventType>,
+    codes: Vec<EventCode>,
+    abs: Vec<(u16, libc::input_absinfo)>,
+    repeat: Option<(i32, i32)>,
+    ff_effects_max: u32,
+}
+
+impl UInputBuilder {
+    /// Start building a device without any capability.
+    pub fn new() -> UInputBuilder {
+        UInputBuilder {
+            name: String::from("evdev-rs uinput device"),
+            phys: None,
+            id: libc::input_id {
+                bustype: BusType::BUS_VIRTUAL as u16,
+                vendor: 0,
+                product: 0,
+                version: 0,
+            },
+            props: Vec::new(),
+            types: Vec::new(),
+            codes: Vec::new(),
+            abs: Vec::new(),
+            repeat: None,
+            ff_effects_max: 0,
+        }
+    }
+
+    /// Set the name of the device.
+    pub fn name(mut self, name: &str) -> UInputBuilder {
+        self.name = name.to_string();
+        self
+    }
+
+    /// Set the physical location of the device.
+    ///
+    /// `build` fails with `Error::InvalidCode` if `phys` contains a NUL byte.
+    pub fn phys(mut self, phys: &str) -> UInputBuilder {
+        self.phys = Some(phys.to_string());
+        self
+    }
+
+    /// Set the bus type, vendor, product and version of the device.
+    pub fn id(mut self, id: &DeviceId) -> UInputBuilder {
+        self.id = libc::input_id {
+            bustype: id.bustype.clone() as u16,
+            vendor: id.vendor,
+            product: id.product,
+            version: id.version,
+        };
+        self
+    }
+
+    /// Set an input property on the device.
+    pub fn property(mut self, prop: &InputProp) -> UInputBuilder {
+        self.props.push(prop.clone());
+        self
+    }
+
+    /// Enable an event type on the device.
+    ///
+    /// Enabling a code with `code`, `abs`, `repeat` or `ff` also enables its
+    /// type, so this is only needed for types without codes, like `EV_PWR`.
+    pub fn event_type(mut self, ev_type: &EventType) -> UInputBuilder {
+        self.types.push(ev_type.clone());
+        self
+    }
+
+    /// Enable an event code, and its type, on the device.
+    ///
+    /// Codes of type `EV_ABS` must be enabled with `abs` instead, which sets up
+    /// the axis, and `EV_REP` with `repeat`.
+    pub fn code(mut self, code: &EventCode) -> UInputBuilder {
+        self.codes.push(code.clone());
+        self
+    }
+
+    /// Enable an absolute axis with the given axis information.
+    pub fn abs(mut self, code: &EV_ABS, absinfo: &AbsInfo) -> UInputBuilder {
+        self.abs.push((code.clone() as u16, absinfo.as_raw()));
+        self
+    }
+
+    /// Enable kernel key repeat with the given delay and period, both in
+    /// milliseconds.
+    pub fn repeat(mut self, delay: i32, period: i32) -> UInputBuilder {
+        self.repeat = Some((delay, period));
+        self
+    }
+
+    /// Enable a force feedback capability on the device.
+    ///
+    /// Unless set with `ff_effects_max`, the device accepts up to 16
+    /// simultaneous effects.
+    pub fn ff(mut self, code: &EV_FF) -> UInputBuilder {
+        self.codes.push(EventCode::EV_FF(code.clone()));
+        if self.ff_effects_max == 0 {
+            self.ff_effects_max = 16;
+        }
+        self
+    }
+
+    /// Set how many force feedback effects can be uploaded at once.
+    pub fn ff_effects_max(mut self, max: u32) -> UInputBuilder {
+        self.ff_effects_max = max;
+        self
+    }
+
+    /// Create the uinput device through `/dev/uinput`.
+    pub fn build(self) -> Result<UInputDevice, Error> {
+        let phys = match self.phys {
+            Some(ref phys) => Some(CString::new(phys.as_str())
+                                   .map_err(|_| Error::InvalidCode(phys.clone()))?),
+            None => None,
+        };
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(libc::O_NONBLOCK | libc::O_CLOEXEC)
+            .open(UINPUT_PATH)
+            .map_err(|e| Error::io("open", e).with_path(Some(UINPUT_PATH)))?;
+        let fd = file.as_raw_fd();
+
+        unsafe {
+            for ev_type in &self.types {
+                ioctl::ui_set_evbit(fd, ev_type.clone() as c_ulong)
+                .map_err(|e| Error::nix("UI_SET_EVBIT", e))?;
+            }
+            for code in &self.codes {
+                self.set_code_bit(fd, code)?;
+            }
+            if !self.abs.is_empty() {
+                ioctl::ui_set_evbit(fd, EventType::EV_ABS as c_ulong)
+                .map_err(|e| Error::nix("UI_SET_EVBIT", e))?;
+            }
+            for &(code, _) in &self.abs {
+                ioctl::ui_set_absbit(fd, code as c_ulong)
+                .map_err(|e| Error::nix("UI_SET_ABSBIT", e))?;
+            }
+            if self.repeat.is_some() {
+                ioctl::ui_set_evbit(fd, EventType::EV_REP as c_ulong)
+                .map_err(|e| Error::nix("UI_SET_EVBIT", e))?;
+            }
+            for prop in &self.props {
+                ioctl::ui_set_propbit(fd, prop.clone() as c_ulong)
+                .map_err(|e| Error::nix("UI_SET_PROPBIT", e))?;
+            }
+            if let Some(ref phys) = phys {
+                ioctl::ui_set_phys(fd, phys.as_ptr())
+                .map_err(|e| Error::nix("UI_SET_PHYS", e))?;
+            }
+        }
+
+        let mut version: c_uint = 0;
+        let has_dev_setup = unsafe { ioctl::ui_get_version(fd, &mut version) }.is_ok()
+                            && version >= ioctl::UINPUT_VERSION_DEV_SETUP;
+        if has_dev_setup {
+            self.dev_setup(&file)?;
+        } else {
+            self.user_dev_setup(&file)?;
+        }
+
+        unsafe {
+            ioctl::ui_dev_create(fd).map_err(|e| Error::nix("UI_DEV_CREATE", e))?;
+        }
+
+        let syspath = sysname(&file).map(|name| format!("/sys/devices/virtual/input/{}", name));
+        let devnode = syspath.as_ref().and_then(|syspath| find_devnode(syspath));
+        let device = UInputDevice {
+            backend: Backend::Kernel { file, syspath, devnode },
+        };
+
+        if let Some((delay, period)) = self.repeat {
+            let time = TimeVal::new(0, 0);
+            device.write_event(&InputEvent::new(&time, &EventCode::EV_REP(EV_REP::REP_DELAY), delay))?;
+            device.write_event(&InputEvent::new(&time, &EventCode::EV_REP(EV_REP::REP_PERIOD), period))?;
+            device.write_event(&InputEvent::new(&time, &EventCode::EV_SYN(EV_SYN::SYN_REPORT), 0))?;
+        }
+
+        Ok(device)
+    }
+
+    unsafe fn set_code_bit(&self, fd: c_int, code: &EventCode) -> Result<(), Error> {
+        let (ev_type, ev_code) = event_code_to_int(code);
+        let ev_code = ev_code as c_ulong;
+
+        ioctl::ui_set_evbit(fd, ev_type as c_ulong)
+                .map_err(|e| Error::nix("UI_SET_EVBIT", e))?;
+        let result = match *code {
+            EventCode::EV_KEY(_) => ioctl::ui_set_keybit(fd, ev_code),
+            EventCode::EV_REL(_) => ioctl::ui_set_relbit(fd, ev_code),
+            EventCode::EV_MSC(_) => ioctl::ui_set_mscbit(fd, ev_code),
+            EventCode::EV_SW(_) => ioctl::ui_set_swbit(fd, ev_code),
+            EventCode::EV_LED(_) => ioctl::ui_set_ledbit(fd, ev_code),
+            EventCode::EV_SND(_) => ioctl::ui_set_sndbit(fd, ev_code),
+            EventCode::EV_FF(_) => ioctl::ui_set_ffbit(fd, ev_code),
+            EventCode::EV_SYN(_) => Ok(0),
+            _ => return Err(Error::UnsupportedCapability(code.to_string())),
+        };
+
+        result.map(|_| ()).map_err(|e| Error::nix("UI_SET_*BIT", e))
+    }
+
+    fn dev_setup(&self, file: &File) -> Result<(), Error> {
+        let mut setup: libc::uinput_setup = unsafe { mem::zeroed() };
+        setup.id = self.id;
+        setup.ff_effects_max = self.ff_effects_max;
+        copy_name(&mut setup.name, &self.name);
+
         unsafe {
-            raw::libevdev_uinput_destroy(self.raw);
+            ioctl::ui_dev_setup(file.as_raw_fd(), &setup)
+                .map_err(|e| Error::nix("UI_DEV_SETUP", e))?;
+            for &(code, absinfo) in &self.abs {
+                let abs_setup = libc::uinput_abs_setup { code, absinfo };
+                ioctl::ui_abs_setup(file.as_raw_fd(), &abs_setup)
+                .map_err(|e| Error::nix("UI_ABS_SETUP", e))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Set up the device on kernels without `UI_DEV_SETUP`.
+    ///
+    /// `uinput_user_dev` cannot carry the axis resolution, so it is lost.
+    fn user_dev_setup(&self, file: &File) -> Result<(), Error> {
+        let mut user_dev: libc::uinput_user_dev = unsafe { mem::zeroed() };
+        user_dev.id = self.id;
+        user_dev.ff_effects_max = self.ff_effects_max;
+        copy_name(&mut user_dev.name, &self.name);
+
+        for &(code, absinfo) in &self.abs {
+            let code = code as usize;
+            user_dev.absmin[code] = absinfo.minimum;
+            user_dev.absmax[code] = absinfo.maximum;
+            user_dev.absfuzz[code] = absinfo.fuzz;
+            user_dev.absflat[code] = absinfo.flat;
         }
+
+        write_struct(file, &user_dev)
+    }
+}
+
+impl Default for UInputBuilder {
+    fn default() -> UInputBuilder {
+        UInputBuilder::new()
+    }
+}
+
+fn errno_to_retval(result: Result<(), Error>) -> i32 {
+    match result {
+        Ok(()) => 0,
+        Err(error) => -(error.errno().unwrap_or(Errno::EINVAL) as i32),
     }
 }
+
+/// Copy `name` into a fixed size, NUL terminated C string buffer.
+fn copy_name(dst: &mut [libc::c_char], name: &str) {
+    let len = name.len().min(dst.len() - 1);
+    for (dst, &b) in dst.iter_mut().zip(name.as_bytes()[..len].iter()) {
+        *dst = b as libc::c_char;
+    }
+}
+
+/// The name of the device in /sys/devices/virtual/input, e.g. `input123`.
+fn sysname(file: &File) -> Option<String> {
+    let mut buf = [0u8; 64];
+    unsafe { ioctl::ui_get_sysname(file.as_raw_fd(), &mut buf) }.ok()?;
+    let len = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+    String::from_utf8(buf[..len].to_vec()).ok()
+}
+
+/// Find the `/dev/input/eventN` node of the input device at `syspath`.
+fn find_devnode(syspath: &str) -> Option<String> {
+    fs::read_dir(syspath).ok()?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .find(|name| name.starts_with("event"))
+        .map(|name| format!("/dev/input/{}", name))
+}
@@ -1,8 +1,17 @@
 use enums::*;
 use libc::{c_char, c_uint};
+use error::Error;
+use nix::errno::Errno;
+use nix::fcntl::{fcntl, FcntlArg, OFlag};
+use nix::unistd::read;
 use raw;
 use std::fmt;
 use std::ffi::{CStr, CString};
+use std::fs::File;
+use std::io::Write;
+use std::mem;
+use std::os::unix::io::RawFd;
+use std::slice;
 
 pub(crate) fn ptr_to_str(ptr: *const c_char) -> Option<&'static str> {
     let slice : Option<&CStr> = unsafe {
@@ -21,6 +30,38 @@ pub(crate) fn ptr_to_str(ptr: *const c_char) -> Option<&'static str> {
     }
 }
 
+/// Write the raw bytes of a C struct to the file.
+pub(crate) fn write_struct<T>(file: &File, data: &T) -> Result<(), Error> {
+    write_structs(file, slice::from_ref(data))
+}
+
+/// Write the raw bytes of an array of C structs to the file, in one write.
+pub(crate) fn write_structs<T>(mut file: &File, data: &[T]) -> Result<(), Error> {
+    let bytes = unsafe {
+        slice::from_raw_parts(data.as_ptr() as *const u8, mem::size_of_val(data))
+    };
+    file.write_all(bytes).map_err(|e| Error::io("write", e))
+}
+
+/// Read the raw bytes of a C struct from the file.
+pub(crate) fn read_struct<T>(fd: RawFd, data: &mut T) -> Result<(), Error> {
+    let bytes = unsafe {
+        slice::from_raw_parts_mut(data as *mut T as *mut u8, mem::size_of::<T>())
+    };
+    match read(fd, bytes).map_err(|e| Error::nix("read", e))? {
+        n if n == bytes.len() => Ok(()),
+        _ => Err(Error::os("read", Errno::EIO)),
+    }
+}
+
+/// Put the file descriptor in O_NONBLOCK mode.
+pub(crate) fn set_nonblocking(fd: RawFd) -> Result<(), Error> {
+    let flags = fcntl(fd, FcntlArg::F_GETFL).map_err(|e| Error::nix("F_GETFL", e))?;
+    let flags = OFlag::from_bits_truncate(flags) | OFlag::O_NONBLOCK;
+    fcntl(fd, FcntlArg::F_SETFL(flags)).map_err(|e| Error::nix("F_SETFL", e))?;
+    Ok(())
+}
+
 pub struct EventTypeIterator {
     current: EventType
 }
@@ -174,6 +215,22 @@ impl fmt::Display for EventCode {
     }
 }
 
+/// An event code displayed by a preferred name, see `EventCode::display_with`.
+pub struct DisplayWith<'a> {
+    code: &'a EventCode,
+    names: &'a [&'a str],
+}
+
+impl<'a> fmt::Display for DisplayWith<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names = self.code.names();
+        match self.names.iter().find(|name| names.contains(name)) {
+            Some(name) => write!(f, "{}", name),
+            None => write!(f, "{}", self.code),
+        }
+    }
+}
+
 impl fmt::Display for InputProp {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}", ptr_to_str(unsafe {
@@ -187,7 +244,7 @@ impl EventType {
         EventTypeIterator { current: self.clone() }
     }
 
-    /// The given type constant for the passed name or Errno if not found.
+    /// The given type constant for the passed name or None if not found.
     pub fn from_str(name: &str) -> Option<EventType> {
         let name = CString::new(name).unwrap();
         let result = unsafe {
@@ -201,7 +258,7 @@ impl EventType {
     }
 
     /// The max value defined for the given event type, e.g. ABS_MAX for a type
-    /// of EV_ABS, or Errno for an invalid type.
+    /// of EV_ABS, or None for an invalid type.
     pub fn get_max(ev_type: &EventType) -> Option<i32> {
         let result = unsafe {
             raw::libevdev_event_type_get_max(ev_type.clone() as c_uint)
@@ -221,19 +278,68 @@ impl EventCode {
 
     /// Look up an event code by its type and name. Event codes start with a fixed
     /// prefix followed by their name (eg., "ABS_X"). The prefix must be included in
-    /// the name. It returns the constant assigned to the event code or Errno if not
-    /// found.
+    /// the name. Kernel aliases (eg., "BTN_GAMEPAD") are resolved too. It returns
+    /// the constant assigned to the event code or None if not found.
     pub fn from_str(ev_type: &EventType, name: &str) -> Option<EventCode> {
-        let name = CString::new(name).unwrap();
+        let c_name = CString::new(name).unwrap();
         let result = unsafe {
-            raw::libevdev_event_code_from_name(ev_type.clone() as c_uint, name.as_ptr())
+            raw::libevdev_event_code_from_name(ev_type.clone() as c_uint, c_name.as_ptr())
         };
 
         match result {
-            -1 => None,
+            -1 => code_aliases(ev_type).into_iter()
+                .find(|&(alias, _)| alias == name)
+                .map(|(_, code)| code),
              k => Some(int_to_event_code(ev_type.clone() as u32, k as u32)),
         }
     }
+
+    /// The names of the event code: the one libevdev knows it by, followed by
+    /// its kernel aliases (eg., "BTN_SOUTH", "BTN_A" and "BTN_GAMEPAD").
+    pub fn names(&self) -> Vec<&'static str> {
+        let (ev_type, ev_code) = event_code_to_int(self);
+        let name = ptr_to_str(unsafe {
+            raw::libevdev_event_code_get_name(ev_type, ev_code)
+        });
+        let aliases = match int_to_event_type(ev_type) {
+            Some(ev_type) => code_aliases(&ev_type),
+            None => Vec::new(),
+        };
+
+        let mut names: Vec<_> = name.into_iter().collect();
+        for (alias, code) in aliases {
+            if code == *self && !names.contains(&alias) {
+                names.push(alias);
+            }
+        }
+        names
+    }
+
+    /// Display the event code by the first of `names` that is one of its
+    /// `names` (eg., "BTN_A" rather than "BTN_SOUTH"), or by the name of
+    /// libevdev if there is none. The same list can be used for all the codes.
+    pub fn display_with<'a>(&'a self, names: &'a [&'a str]) -> DisplayWith<'a> {
+        DisplayWith { code: self, names }
+    }
+}
+
+/// The kernel aliases of the codes of an event type, with the codes they
+/// stand for.
+fn code_aliases(ev_type: &EventType) -> Vec<(&'static str, EventCode)> {
+    macro_rules! aliases {
+        ($($ev_type:ident),*) => {
+            match *ev_type {
+                $(
+                    EventType::$ev_type => $ev_type::ALIASES.iter()
+                        .map(|&(name, ref code)| (name, EventCode::$ev_type(code.clone())))
+                        .collect(),
+                )*
+                _ => Vec::new(),
+            }
+        };
+    }
+
+    aliases!(EV_SYN, EV_KEY, EV_REL, EV_ABS, EV_MSC, EV_SW, EV_LED, EV_SND, EV_REP, EV_FF)
 }
 
 impl InputProp {
@@ -244,7 +350,7 @@ impl InputProp {
     /// Look up an input property by its name. Properties start with the fixed
     /// prefix "INPUT_PROP_" followed by their name (eg., "INPUT_PROP_POINTER").
     /// The prefix must be included in the name. It returns the constant assigned
-    /// to the property or Errno if not found.
+    /// to the property or None if not found.
     pub fn from_str(name: &str) -> Option<InputProp> {
         let name = CString::new(name).unwrap();
         let result = unsafe {
@@ -1,4 +1,12 @@
 extern crate evdev_rs as evdev;
+#[cfg(feature = "tokio")]
+extern crate futures_core;
+#[cfg(feature = "tokio")]
+extern crate tokio;
+#[cfg(feature = "serde")]
+extern crate bincode;
+#[cfg(feature = "serde")]
+extern crate serde_json;
 
 use evdev::*;
 use evdev::enums::*;
@@ -24,7 +32,7 @@ fn context_set_fd() {
 
     match d.set_fd(f) {
         Ok(()) => ..,
-        Err(result) => panic!("Error {}", result.desc()),
+        Err(result) => panic!("Error {}", result),
     };
 }
 
@@ -165,3 +173,1264 @@ fn device_get_value() {
 fn check_event_name() {
    assert_eq!("EV_ABS", EventType::EV_ABS.to_string());
 }
+
+#[test]
+fn event_code_aliases() {
+    assert_eq!(EV_KEY::BTN_A, EV_KEY::BTN_SOUTH);
+    assert_eq!(EV_KEY::BTN_MISC, EV_KEY::BTN_0);
+    assert!(EV_KEY::ALIASES.contains(&("BTN_GAMEPAD", EV_KEY::BTN_SOUTH)));
+
+    let south = EventCode::EV_KEY(EV_KEY::BTN_SOUTH);
+    assert_eq!(EventCode::from_str(&EventType::EV_KEY, "BTN_GAMEPAD"), Some(south.clone()));
+    assert_eq!(EventCode::from_str(&EventType::EV_KEY, "KEY_MIN_INTERESTING"),
+               Some(EventCode::EV_KEY(EV_KEY::KEY_MUTE)));
+    assert_eq!(EventCode::from_str(&EventType::EV_ABS, "BTN_GAMEPAD"), None);
+
+    let names = south.names();
+    assert!(names.contains(&"BTN_A") && names.contains(&"BTN_GAMEPAD"));
+
+    let gamepad = ["KEY_A", "BTN_A", "BTN_B"];
+    assert_eq!(south.display_with(&gamepad).to_string(), "BTN_A");
+    assert_eq!(EventCode::EV_KEY(EV_KEY::BTN_EAST).display_with(&gamepad).to_string(), "BTN_B");
+    assert_eq!(EventCode::EV_KEY(EV_KEY::BTN_NORTH).display_with(&gamepad).to_string(), "BTN_NORTH");
+    assert_eq!(south.to_string(), names[0]);
+}
+
+#[test]
+fn enumerate_devices() {
+    let enumeration = enumerate().unwrap();
+    let path = std::path::Path::new("/dev/input/event0");
+
+    assert!(enumeration.devices.iter().any(|&(ref p, _)| p == path));
+}
+
+#[test]
+fn enumerate_filter_name() {
+    let enumeration = Enumerator::new()
+        .name("evdev-rs nonexistent device")
+        .scan()
+        .unwrap();
+
+    assert!(enumeration.devices.is_empty());
+}
+
+fn monitor_test_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("evdev-rs-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn monitor_inotify() {
+    let dir = monitor_test_dir("inotify");
+    let node = dir.join("event99");
+    let mut monitor = Monitor::with_path(&dir).unwrap();
+    let timeout = Some(std::time::Duration::from_secs(1));
+
+    std::os::unix::fs::symlink("/dev/input/event0", &node).unwrap();
+    match monitor.next_event(timeout).unwrap() {
+        MonitorEvent::Added(path, _) => assert_eq!(path, node),
+        MonitorEvent::Removed(_) => panic!("expected Added"),
+    }
+
+    std::fs::remove_file(&node).unwrap();
+    match monitor.next_event(timeout).unwrap() {
+        MonitorEvent::Removed(path) => assert_eq!(path, node),
+        MonitorEvent::Added(..) => panic!("expected Removed"),
+    }
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn monitor_timeout_ignores_other_uevents() {
+    use std::os::unix::net::UnixDatagram;
+    use std::time::{Duration, Instant};
+
+    let dir = monitor_test_dir("timeout");
+    let (kernel, source) = UnixDatagram::pair().unwrap();
+    let mut monitor = Monitor::with_path(&dir).unwrap().with_uevent_source(source);
+
+    // Uevents which are not for input devices keep waking the monitor up.
+    let sender = std::thread::spawn(move || {
+        let start = Instant::now();
+        while start.elapsed() < Duration::from_secs(2) {
+            let uevent = b"change@/devices/virtual/net/lo\0ACTION=change\0SUBSYSTEM=net\0";
+            if kernel.send(uevent).is_err() {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+    });
+
+    let start = Instant::now();
+    match monitor.next_event(Some(Duration::from_millis(200))) {
+        Err(Error::WouldBlock) => (),
+        result => panic!("expected a timeout, got {:?}", result.map(|_| ())),
+    }
+    assert!(start.elapsed() < Duration::from_secs(1));
+
+    drop(monitor);
+    sender.join().unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn monitor_uevents() {
+    use std::os::unix::net::UnixDatagram;
+
+    let dir = monitor_test_dir("uevents");
+    let node = dir.join("event98");
+    std::os::unix::fs::symlink("/dev/input/event0", &node).unwrap();
+
+    let (kernel, source) = UnixDatagram::pair().unwrap();
+    let mut monitor = Monitor::with_path(&dir).unwrap().with_uevent_source(source);
+    let timeout = Some(std::time::Duration::from_secs(1));
+
+    kernel.send(b"add@/devices/virtual/input/input98/event98\0ACTION=add\0\
+                  SUBSYSTEM=input\0DEVNAME=input/event98\0").unwrap();
+    match monitor.next_event(timeout).unwrap() {
+        MonitorEvent::Added(path, _) => assert_eq!(path, node),
+        MonitorEvent::Removed(_) => panic!("expected Added"),
+    }
+
+    kernel.send(b"remove@/devices/virtual/input/input98/event98\0ACTION=remove\0\
+                  SUBSYSTEM=input\0DEVNAME=input/event98\0").unwrap();
+    match monitor.next_event(timeout).unwrap() {
+        MonitorEvent::Removed(path) => assert_eq!(path, node),
+        MonitorEvent::Added(..) => panic!("expected Removed"),
+    }
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(feature = "tokio")]
+#[test]
+fn event_stream_uinput() {
+    use futures_core::Stream;
+    use std::pin::Pin;
+
+    let d = Device::new().unwrap();
+    d.set_name("evdev-rs event stream test");
+    d.enable(&EventCode::EV_KEY(EV_KEY::KEY_A)).unwrap();
+    let uinput = UInputDevice::create_from_device(&d).unwrap();
+
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_io()
+        .build()
+        .unwrap();
+    let _guard = rt.enter();
+
+    let f = File::open(uinput.devnode().unwrap()).unwrap();
+    let mut stream = EventStream::new(Device::new_from_fd(f).unwrap()).unwrap();
+
+    let time = TimeVal::new(0, 0);
+    uinput.write_event(&InputEvent::new(&time, &EventCode::EV_KEY(EV_KEY::KEY_A), 1)).unwrap();
+    uinput.write_event(&InputEvent::new(&time, &EventCode::EV_SYN(EV_SYN::SYN_REPORT), 0)).unwrap();
+
+    let mut next = || rt.block_on(std::future::poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)));
+    let (_, ev) = next().unwrap().unwrap();
+    assert_eq!(ev.event_code, EventCode::EV_KEY(EV_KEY::KEY_A));
+    assert_eq!(ev.value, 1);
+    let (_, ev) = next().unwrap().unwrap();
+    assert_eq!(ev.event_code, EventCode::EV_SYN(EV_SYN::SYN_REPORT));
+}
+
+#[test]
+fn event_loop_timer() {
+    use std::time::{Duration, Instant};
+
+    let mut event_loop = EventLoop::new().unwrap();
+    let start = Instant::now();
+    let once = event_loop.add_timer(Duration::from_millis(20), None);
+    let cancelled = event_loop.add_timer(Duration::from_millis(10), None);
+    assert!(event_loop.cancel_timer(cancelled));
+
+    match event_loop.next_event(Some(Duration::from_secs(1))).unwrap() {
+        LoopEvent::Timer(token) => assert_eq!(token, once),
+        _ => panic!("expected a timer"),
+    }
+    assert!(start.elapsed() >= Duration::from_millis(20));
+
+    match event_loop.next_event(Some(Duration::from_millis(10))) {
+        Err(e) => assert_eq!(e, Error::WouldBlock),
+        Ok(_) => panic!("expected a timeout"),
+    }
+}
+
+#[test]
+fn event_loop_uinput() {
+    let d = Device::new().unwrap();
+    d.set_name("evdev-rs event loop test");
+    d.enable(&EventCode::EV_KEY(EV_KEY::KEY_B)).unwrap();
+    let uinput = UInputDevice::create_from_device(&d).unwrap();
+
+    let mut event_loop = EventLoop::new().unwrap();
+    let f = File::open(uinput.devnode().unwrap()).unwrap();
+    let token = event_loop.add(Device::new_from_fd(f).unwrap()).unwrap();
+
+    let time = TimeVal::new(0, 0);
+    uinput.write_event(&InputEvent::new(&time, &EventCode::EV_KEY(EV_KEY::KEY_B), 1)).unwrap();
+    uinput.write_event(&InputEvent::new(&time, &EventCode::EV_SYN(EV_SYN::SYN_REPORT), 0)).unwrap();
+
+    match event_loop.next_event(Some(std::time::Duration::from_secs(1))).unwrap() {
+        LoopEvent::Event(t, ev) => {
+            assert_eq!(t, token);
+            assert_eq!(ev.event_code, EventCode::EV_KEY(EV_KEY::KEY_B));
+        },
+        _ => panic!("expected an event"),
+    }
+
+    drop(uinput);
+    loop {
+        match event_loop.next_event(Some(std::time::Duration::from_secs(1))).unwrap() {
+            LoopEvent::Removed(t, _) => {
+                assert_eq!(t, token);
+                break;
+            },
+            _ => continue,
+        }
+    }
+}
+
+#[test]
+fn uinput_builder() {
+    let absinfo = AbsInfo {
+        value: 0,
+        minimum: -100,
+        maximum: 100,
+        fuzz: 0,
+        flat: 0,
+        resolution: 10,
+    };
+    let uinput = UInputBuilder::new()
+        .name("evdev-rs builder test")
+        .id(&DeviceId { bustype: BusType::BUS_USB, vendor: 0x1234, product: 0x5678, version: 1 })
+        .property(&InputProp::INPUT_PROP_POINTER)
+        .code(&EventCode::EV_KEY(EV_KEY::BTN_LEFT))
+        .code(&EventCode::EV_REL(EV_REL::REL_WHEEL))
+        .abs(&EV_ABS::ABS_X, &absinfo)
+        .build()
+        .unwrap();
+
+    let f = File::open(uinput.devnode().unwrap()).unwrap();
+    let d = Device::new_from_fd(f).unwrap();
+
+    assert_eq!(d.name().unwrap(), "evdev-rs builder test");
+    assert_eq!(d.vendor_id(), 0x1234);
+    assert_eq!(d.product_id(), 0x5678);
+    assert!(d.has(&InputProp::INPUT_PROP_POINTER));
+    assert!(d.has(&EventCode::EV_KEY(EV_KEY::BTN_LEFT)));
+    assert!(d.has(&EventCode::EV_REL(EV_REL::REL_WHEEL)));
+    let abs = d.abs_info(&EventCode::EV_ABS(EV_ABS::ABS_X)).unwrap();
+    assert_eq!((abs.minimum, abs.maximum, abs.resolution), (-100, 100, 10));
+}
+
+#[test]
+fn uinput_builder_invalid_phys() {
+    match UInputBuilder::new().phys("usb-0000:00:14.0-1\0/input0").build() {
+        Err(Error::InvalidCode(phys)) => assert_eq!(phys, "usb-0000:00:14.0-1\0/input0"),
+        result => panic!("expected InvalidCode, got {:?}", result.map(|_| ())),
+    }
+}
+
+#[test]
+fn ff_effect_raw_roundtrip() {
+    use evdev::ff::*;
+
+    let envelope = Envelope { attack_length: 1, attack_level: 2, fade_length: 3, fade_level: 4 };
+    let kinds = [
+        EffectKind::Rumble { strong_magnitude: 0x8000, weak_magnitude: 0x4000 },
+        EffectKind::Periodic { waveform: Waveform::Sine, period: 100, magnitude: -5,
+                               offset: 6, phase: 7, envelope },
+        EffectKind::Constant { level: -300, envelope },
+        EffectKind::Ramp { start_level: -1, end_level: 1, envelope },
+        EffectKind::Damper([Condition { right_saturation: 1, left_saturation: 2, right_coeff: -3,
+                                        left_coeff: 4, deadband: 5, center: -6 },
+                            Condition::default()]),
+    ];
+
+    for kind in kinds.iter() {
+        let mut effect = Effect::new(*kind);
+        effect.direction = 0x4000;
+        effect.trigger = Trigger { button: 0x120, interval: 10 };
+        effect.replay = Replay { length: 500, delay: 20 };
+
+        assert_eq!(Effect::from_raw(&effect.as_raw()), Some(effect));
+    }
+}
+
+#[test]
+fn ff_effects_max() {
+    let uinput = UInputBuilder::new()
+        .name("evdev-rs ff test")
+        .ff(&EV_FF::FF_RUMBLE)
+        .ff_effects_max(8)
+        .build()
+        .unwrap();
+
+    let f = File::open(uinput.devnode().unwrap()).unwrap();
+    let d = Device::new_from_fd(f).unwrap();
+
+    assert!(d.has(&EventCode::EV_FF(EV_FF::FF_RUMBLE)));
+    assert_eq!(d.ff_effects_max().unwrap(), 8);
+}
+
+#[test]
+fn uinput_ff_requests() {
+    use evdev::ff::{Effect, EffectKind};
+    use std::fs::OpenOptions;
+    use std::time::{Duration, Instant};
+
+    let uinput = UInputBuilder::new()
+        .name("evdev-rs ff server test")
+        .ff(&EV_FF::FF_RUMBLE)
+        .build()
+        .unwrap();
+    let devnode = uinput.devnode().unwrap().to_string();
+
+    let client = std::thread::spawn(move || {
+        let f = OpenOptions::new().read(true).write(true).open(devnode).unwrap();
+        let d = Device::new_from_fd(f).unwrap();
+        let effect = Effect::new(EffectKind::Rumble { strong_magnitude: 0x1234, weak_magnitude: 0 });
+        let id = d.upload_effect(&effect).unwrap();
+        d.play_effect(id, 3).unwrap();
+        d.erase_effect(id).unwrap();
+    });
+
+    let mut requests = Vec::new();
+    let start = Instant::now();
+    while requests.len() < 3 && start.elapsed() < Duration::from_secs(5) {
+        uinput.handle_requests(|request| {
+            requests.push(request);
+            Ok(())
+        }).unwrap();
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    client.join().unwrap();
+
+    let id = match requests[0] {
+        UInputRequest::Upload(effect) => {
+            assert_eq!(effect.kind, EffectKind::Rumble { strong_magnitude: 0x1234, weak_magnitude: 0 });
+            effect.id
+        },
+        ref other => panic!("expected an upload, got {:?}", other),
+    };
+    assert_eq!(requests[1], UInputRequest::Play(id, 3));
+    assert_eq!(requests[2], UInputRequest::Erase(id));
+}
+
+#[test]
+fn uinput_other_requests() {
+    use std::fs::OpenOptions;
+    use std::time::{Duration, Instant};
+
+    let capsl = EventCode::EV_LED(EV_LED::LED_CAPSL);
+    let uinput = UInputBuilder::new()
+        .name("evdev-rs led test")
+        .code(&capsl)
+        .build()
+        .unwrap();
+
+    let f = OpenOptions::new().read(true).write(true).open(uinput.devnode().unwrap()).unwrap();
+    let d = Device::new_from_fd(f).unwrap();
+    d.kernel_set_led_value(&capsl, LedState::On).unwrap();
+
+    let mut requests = Vec::new();
+    let start = Instant::now();
+    while requests.is_empty() && start.elapsed() < Duration::from_secs(5) {
+        uinput.handle_requests(|request| {
+            if let UInputRequest::Other(ref event) = request {
+                if event.event_code == capsl {
+                    requests.push(event.value);
+                }
+            }
+            Ok(())
+        }).unwrap();
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(requests, [1]);
+}
+
+#[test]
+fn mt_tracker_frames() {
+    use evdev::mt::Touch;
+
+    let time = TimeVal::new(0, 0);
+    let abs = |code, value| InputEvent::new(&time, &EventCode::EV_ABS(code), value);
+    let syn = InputEvent::new(&time, &EventCode::EV_SYN(EV_SYN::SYN_REPORT), 0);
+    let mut tracker = MtTracker::with_slots(2);
+
+    assert!(tracker.process(&abs(EV_ABS::ABS_MT_TRACKING_ID, 7)).is_empty());
+    tracker.process(&abs(EV_ABS::ABS_MT_POSITION_X, 10));
+    tracker.process(&abs(EV_ABS::ABS_MT_POSITION_Y, 20));
+    let down = Touch { slot: 0, tracking_id: 7, x: 10, y: 20, ..Touch::default() };
+    assert_eq!(tracker.process(&syn), vec![TouchEvent::TouchDown(down)]);
+
+    tracker.process(&abs(EV_ABS::ABS_MT_POSITION_X, 11));
+    tracker.process(&abs(EV_ABS::ABS_MT_SLOT, 1));
+    tracker.process(&abs(EV_ABS::ABS_MT_TRACKING_ID, 8));
+    let moved = Touch { x: 11, ..down };
+    let second = Touch { slot: 1, tracking_id: 8, ..Touch::default() };
+    assert_eq!(tracker.process(&syn),
+               vec![TouchEvent::TouchMotion(moved), TouchEvent::TouchDown(second)]);
+    assert_eq!(tracker.touches().count(), 2);
+
+    // A contact replaced within a single frame, as in a sync delta.
+    tracker.process(&abs(EV_ABS::ABS_MT_SLOT, 0));
+    tracker.process(&abs(EV_ABS::ABS_MT_TRACKING_ID, -1));
+    tracker.process(&abs(EV_ABS::ABS_MT_TRACKING_ID, 9));
+    tracker.process(&abs(EV_ABS::ABS_MT_SLOT, 1));
+    tracker.process(&abs(EV_ABS::ABS_MT_TRACKING_ID, -1));
+    let replaced = Touch { tracking_id: 9, ..moved };
+    assert_eq!(tracker.process(&syn),
+               vec![TouchEvent::TouchUp(moved), TouchEvent::TouchDown(replaced),
+                    TouchEvent::TouchUp(second)]);
+}
+
+#[test]
+fn mt_protocol_a_conversion() {
+    let time = TimeVal::new(0, 0);
+    let ev = |code, value| InputEvent::new(&time, &code, value);
+    let abs = |code, value| ev(EventCode::EV_ABS(code), value);
+    let mt_report = ev(EventCode::EV_SYN(EV_SYN::SYN_MT_REPORT), 0);
+    let syn = ev(EventCode::EV_SYN(EV_SYN::SYN_REPORT), 0);
+
+    let mut converter = ProtocolAConverter::new(4);
+    let mut tracker = MtTracker::with_slots(4);
+    let mut frame = |events: &[InputEvent]| {
+        let mut converted = Vec::new();
+        for event in events {
+            converted.extend(converter.process(event));
+        }
+        let touches: Vec<_> = converted.iter().flat_map(|e| tracker.process(e)).collect();
+        (converted, touches)
+    };
+
+    let (converted, touches) = frame(&[abs(EV_ABS::ABS_MT_POSITION_X, 10),
+                                       abs(EV_ABS::ABS_MT_POSITION_Y, 10),
+                                       mt_report.clone(),
+                                       ev(EventCode::EV_KEY(EV_KEY::BTN_TOUCH), 1),
+                                       syn.clone()]);
+    assert_eq!(converted, vec![abs(EV_ABS::ABS_MT_SLOT, 0),
+                               abs(EV_ABS::ABS_MT_TRACKING_ID, 0),
+                               abs(EV_ABS::ABS_MT_POSITION_X, 10),
+                               abs(EV_ABS::ABS_MT_POSITION_Y, 10),
+                               ev(EventCode::EV_KEY(EV_KEY::BTN_TOUCH), 1),
+                               syn.clone()]);
+    assert_eq!(touches.len(), 1);
+
+    // A second contact appears, listed first, and the first one moves.
+    let (converted, touches) = frame(&[abs(EV_ABS::ABS_MT_POSITION_X, 500),
+                                       abs(EV_ABS::ABS_MT_POSITION_Y, 500),
+                                       mt_report.clone(),
+                                       abs(EV_ABS::ABS_MT_POSITION_X, 12),
+                                       abs(EV_ABS::ABS_MT_POSITION_Y, 10),
+                                       mt_report.clone(),
+                                       syn.clone()]);
+    assert_eq!(converted, vec![abs(EV_ABS::ABS_MT_POSITION_X, 12),
+                               abs(EV_ABS::ABS_MT_SLOT, 1),
+                               abs(EV_ABS::ABS_MT_TRACKING_ID, 1),
+                               abs(EV_ABS::ABS_MT_POSITION_X, 500),
+                               abs(EV_ABS::ABS_MT_POSITION_Y, 500),
+                               syn.clone()]);
+    match (touches[0], touches[1]) {
+        (TouchEvent::TouchMotion(a), TouchEvent::TouchDown(b)) => {
+            assert_eq!((a.tracking_id, a.x), (0, 12));
+            assert_eq!((b.tracking_id, b.x), (1, 500));
+        },
+        other => panic!("unexpected touches {:?}", other),
+    }
+
+    // All contacts lifted.
+    let (converted, touches) = frame(&[mt_report.clone(), syn.clone()]);
+    assert_eq!(converted, vec![abs(EV_ABS::ABS_MT_SLOT, 0),
+                               abs(EV_ABS::ABS_MT_TRACKING_ID, -1),
+                               abs(EV_ABS::ABS_MT_SLOT, 1),
+                               abs(EV_ABS::ABS_MT_TRACKING_ID, -1),
+                               syn]);
+    assert_eq!(tracker.touches().count(), 0);
+    assert_eq!(touches.len(), 2);
+}
+
+#[test]
+fn device_next_frame() {
+    let d = Device::new().unwrap();
+    d.set_name("evdev-rs frame test");
+    d.enable(&EventCode::EV_KEY(EV_KEY::KEY_A)).unwrap();
+    d.enable(&EventCode::EV_KEY(EV_KEY::KEY_B)).unwrap();
+    let uinput = UInputDevice::create_from_device(&d).unwrap();
+
+    let f = File::open(uinput.devnode().unwrap()).unwrap();
+    let d = Device::new_from_fd(f).unwrap();
+    let time = TimeVal::new(0, 0);
+    let key = |code, value| InputEvent::new(&time, &EventCode::EV_KEY(code), value);
+    let syn = InputEvent::new(&time, &EventCode::EV_SYN(EV_SYN::SYN_REPORT), 0);
+
+    // The delta computed by a forced sync is a synthetic frame.
+    uinput.write_event(&key(EV_KEY::KEY_A, 1)).unwrap();
+    uinput.write_event(&syn).unwrap();
+    let frame = d.next_frame(ReadFlag::NORMAL | ReadFlag::FORCE_SYNC).unwrap();
+    assert!(frame.synthetic);
+    assert_eq!(frame.events.len(), 1);
+    assert_eq!(frame.events[0].event_code, EventCode::EV_KEY(EV_KEY::KEY_A));
+
+    uinput.write_event(&key(EV_KEY::KEY_A, 0)).unwrap();
+    uinput.write_event(&key(EV_KEY::KEY_B, 1)).unwrap();
+    uinput.write_event(&syn).unwrap();
+    let frame = d.frames().next().unwrap().unwrap();
+    assert!(!frame.synthetic);
+    let codes: Vec<_> = frame.events.iter().map(|e| (e.event_code.clone(), e.value)).collect();
+    assert_eq!(codes, vec![(EventCode::EV_KEY(EV_KEY::KEY_A), 0),
+                           (EventCode::EV_KEY(EV_KEY::KEY_B), 1)]);
+}
+
+/// Create a uinput keyboard and open it non-blocking.
+fn reader_test_device(name: &str) -> (UInputDevice, Device) {
+    use std::fs::OpenOptions;
+    use std::os::unix::fs::OpenOptionsExt;
+
+    let d = Device::new().unwrap();
+    d.set_name(name);
+    d.enable(&EventCode::EV_KEY(EV_KEY::KEY_A)).unwrap();
+    let uinput = UInputDevice::create_from_device(&d).unwrap();
+
+    let f = OpenOptions::new()
+        .read(true)
+        .custom_flags(nix::libc::O_NONBLOCK)
+        .open(uinput.devnode().unwrap())
+        .unwrap();
+    (uinput, Device::new_from_fd(f).unwrap())
+}
+
+/// Write enough key presses and releases to overflow the kernel buffer,
+/// leaving the key pressed.
+fn reader_flood(uinput: &UInputDevice) {
+    let time = TimeVal::new(0, 0);
+    let syn = InputEvent::new(&time, &EventCode::EV_SYN(EV_SYN::SYN_REPORT), 0);
+    for i in 0..501 {
+        uinput.write_event(&InputEvent::new(&time, &EventCode::EV_KEY(EV_KEY::KEY_A), (i + 1) % 2))
+              .unwrap();
+        uinput.write_event(&syn).unwrap();
+    }
+}
+
+#[test]
+fn reader_deliver_deltas() {
+    let (uinput, d) = reader_test_device("evdev-rs reader deliver test");
+    reader_flood(&uinput);
+
+    let mut reader = Reader::new(d);
+    let events: Vec<_> = (&mut reader).map(|e| e.unwrap()).collect();
+    let resynced = events.iter().position(|e| *e == ReaderEvent::Resynced)
+                         .expect("no resync");
+    assert!(events[..resynced].iter().any(|e| match *e {
+        ReaderEvent::Delta(ref event) => {
+            event.event_code == EventCode::EV_KEY(EV_KEY::KEY_A) && event.value == 1
+        },
+        _ => false,
+    }), "KEY_A not in the delta");
+    assert!(events[resynced..].iter().all(|e| !matches!(*e, ReaderEvent::Delta(_))));
+    assert_eq!(reader.device().event_value(&EventCode::EV_KEY(EV_KEY::KEY_A)), Some(1));
+}
+
+#[test]
+fn reader_drop_and_callback() {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    let (uinput, d) = reader_test_device("evdev-rs reader drop test");
+    reader_flood(&uinput);
+    let mut reader = Reader::with_policy(d, SyncPolicy::Drop);
+    let mut resynced = false;
+    for event in &mut reader {
+        match event.unwrap() {
+            ReaderEvent::Delta(_) => panic!("delta delivered with SyncPolicy::Drop"),
+            ReaderEvent::Resynced => resynced = true,
+            ReaderEvent::Event(_) => (),
+        }
+    }
+    assert!(resynced);
+    assert_eq!(reader.device().event_value(&EventCode::EV_KEY(EV_KEY::KEY_A)), Some(1));
+
+    let (uinput, d) = reader_test_device("evdev-rs reader callback test");
+    reader_flood(&uinput);
+    let calls = Rc::new(Cell::new(0));
+    let counter = calls.clone();
+    let reader = Reader::with_policy(d, SyncPolicy::Callback(Box::new(move |_, _| {
+        counter.set(counter.get() + 1);
+    })));
+    for event in reader {
+        if let ReaderEvent::Delta(_) = event.unwrap() {
+            panic!("delta delivered with SyncPolicy::Callback");
+        }
+    }
+    assert!(calls.get() >= 1);
+}
+
+#[test]
+fn keymap_parse_hwdb() {
+    use evdev::keymap::parse_hwdb;
+
+    let hwdb = "# comment\n\
+                evdev:input:b0003v046DpC52B*\n\
+                evdev:input:b0003v046DpC52D*\n \
+                KEYBOARD_KEY_c0222=f20\n \
+                KEYBOARD_KEY_70039=!leftctrl\n \
+                KEYBOARD_KEY_90001=btn_left\n \
+                KEYBOARD_KEY_10=30\n \
+                KEYBOARD_KEY_11=notakey\n\
+                \n\
+                evdev:atkbd:dmi:bvn*:svnAcme*:pn[Ll]aptop*\n \
+                KEYBOARD_KEY_a0=mute\n\
+                evdev:name:No keys:*\n \
+                KEYBOARD_LED_CAPSLOCK=0\n";
+
+    let entries = parse_hwdb(hwdb);
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].patterns, vec!["evdev:input:b0003v046DpC52B*",
+                                         "evdev:input:b0003v046DpC52D*"]);
+    assert_eq!(entries[0].keys, vec![(0xc0222, EV_KEY::KEY_F20),
+                                     (0x70039, EV_KEY::KEY_LEFTCTRL),
+                                     (0x90001, EV_KEY::BTN_LEFT),
+                                     (0x10, EV_KEY::KEY_A)]);
+    assert_eq!(entries[1].patterns, vec!["evdev:atkbd:dmi:bvn*:svnAcme*:pn[Ll]aptop*"]);
+    assert_eq!(entries[1].keys, vec![(0xa0, EV_KEY::KEY_MUTE)]);
+
+    assert!(entries[0].matches("evdev:input:b0003v046DpC52De0111-e0,1,4,11,14,k71,"));
+    assert!(!entries[0].matches("evdev:input:b0003v046DpC52Ce0111-e0,1,4,11,14,k71,"));
+    assert!(entries[1].matches("evdev:atkbd:dmi:bvnAcme:bvr1.0:svnAcme Inc.:pnlaptop 3:"));
+    assert!(!entries[1].matches("evdev:atkbd:dmi:bvnAcme:bvr1.0:svnAcme Inc.:pnDesktop:"));
+}
+
+#[test]
+fn keymap_modalias() {
+    use evdev::keymap::{modalias, parse_hwdb};
+
+    let uinput = UInputBuilder::new()
+        .name("evdev-rs modalias test")
+        .id(&DeviceId {
+            bustype: BusType::BUS_USB,
+            vendor: 0x046d,
+            product: 0xc52b,
+            version: 0x111,
+        })
+        .code(&EventCode::EV_KEY(EV_KEY::KEY_A))
+        .code(&EventCode::EV_KEY(EV_KEY::KEY_MUTE))
+        .build()
+        .unwrap();
+
+    let d = Device::new_from_fd(File::open(uinput.devnode().unwrap()).unwrap()).unwrap();
+    let modalias = modalias(&d);
+    assert!(modalias.starts_with("evdev:input:b0003v046DpC52Be0111-e0,1,"), "{}", modalias);
+    assert!(modalias.contains("k71,r"), "{}", modalias);
+
+    let entries = parse_hwdb("evdev:input:b0003v046DpC52B*\n KEYBOARD_KEY_70039=leftctrl\n\
+                              evdev:input:b0003v046DpC52C*\n KEYBOARD_KEY_70039=rightctrl\n");
+    let keys: Vec<_> = entries.iter().filter(|entry| entry.matches(&modalias)).collect();
+    assert_eq!(keys.len(), 1);
+    assert_eq!(keys[0].keys, vec![(0x70039, EV_KEY::KEY_LEFTCTRL)]);
+}
+
+#[test]
+fn keymap_get_set_keycode() {
+    use std::fs::OpenOptions;
+
+    // uinput devices have no keymap.
+    let uinput = UInputBuilder::new()
+        .name("evdev-rs keymap test")
+        .code(&EventCode::EV_KEY(EV_KEY::KEY_A))
+        .build()
+        .unwrap();
+    let d = Device::new_from_fd(File::open(uinput.devnode().unwrap()).unwrap()).unwrap();
+    assert_eq!(d.keymap_entries().unwrap(), vec![]);
+    assert_eq!(d.get_keycode(&0x1eu32.to_ne_bytes()).unwrap_err().errno(),
+               Some(nix::errno::Errno::EINVAL));
+    assert_eq!(d.get_keycode(&[]), Err(Error::InvalidCode("scancode of 0 bytes".to_string())));
+    assert!(d.set_keycode(&0x1eu32.to_ne_bytes(), &EV_KEY::KEY_B).is_err());
+
+    let f = OpenOptions::new().read(true).write(true).open("/dev/input/event0").unwrap();
+    let d = Device::new_from_fd(f).unwrap();
+    let entries = d.keymap_entries().unwrap();
+    for (i, entry) in entries.iter().enumerate() {
+        assert_eq!(entry.index as usize, i);
+        assert_eq!(d.get_keycode(&entry.scancode).unwrap(), entry.keycode);
+    }
+
+    // Setting a scancode to its current key leaves the keymap unchanged.
+    if let Some(entry) = entries.iter().find(|entry| matches!(entry.keycode, EventCode::EV_KEY(_))) {
+        if let EventCode::EV_KEY(ref key) = entry.keycode {
+            d.set_keycode(&entry.scancode, key).unwrap();
+        }
+        assert_eq!(d.keymap_entries().unwrap(), entries);
+    }
+}
+
+#[test]
+fn kernel_repeat_settings() {
+    let uinput = UInputBuilder::new()
+        .name("evdev-rs repeat test")
+        .code(&EventCode::EV_KEY(EV_KEY::KEY_A))
+        .repeat(400, 40)
+        .build()
+        .unwrap();
+
+    let f = File::open(uinput.devnode().unwrap()).unwrap();
+    let d = Device::new_from_fd(f).unwrap();
+    assert_eq!(d.repeat_settings().unwrap(), (400, 40));
+
+    d.set_kernel_repeat(500, 50).unwrap();
+    assert_eq!(d.repeat_settings().unwrap(), (500, 50));
+    assert_eq!(d.event_value(&EventCode::EV_REP(EV_REP::REP_DELAY)), Some(500));
+
+    assert_eq!(d.set_kernel_repeat(-1, 50),
+               Err(Error::InvalidCode("REP_DELAY of -1 ms".to_string())));
+    assert_eq!(d.set_kernel_repeat(500, -1),
+               Err(Error::InvalidCode("REP_PERIOD of -1 ms".to_string())));
+    assert_eq!(d.repeat_settings().unwrap(), (500, 50));
+}
+
+#[test]
+fn software_key_repeat() {
+    use evdev::repeat::KeyRepeater;
+    use std::time::Duration;
+
+    let mut event_loop = EventLoop::new().unwrap();
+    let mut repeater = KeyRepeater::new(Duration::from_millis(10), Duration::from_millis(5));
+    let time = TimeVal::new(0, 0);
+
+    repeater.process(&mut event_loop, &InputEvent::new(&time, &EventCode::EV_KEY(EV_KEY::KEY_Z), 1));
+    assert_eq!(repeater.key(), Some(&EV_KEY::KEY_Z));
+
+    for _ in 0..2 {
+        match event_loop.next_event(Some(Duration::from_secs(1))).unwrap() {
+            LoopEvent::Timer(token) => {
+                let events = repeater.timer(token);
+                assert_eq!(events[0].event_code, EventCode::EV_KEY(EV_KEY::KEY_Z));
+                assert_eq!(events[0].value, 2);
+                assert_eq!(events[1].event_code, EventCode::EV_SYN(EV_SYN::SYN_REPORT));
+            },
+            _ => panic!("expected a timer"),
+        }
+    }
+
+    repeater.process(&mut event_loop, &InputEvent::new(&time, &EventCode::EV_KEY(EV_KEY::KEY_Z), 0));
+    assert_eq!(repeater.key(), None);
+    assert!(event_loop.next_event(Some(Duration::from_millis(30))).is_err());
+}
+
+#[test]
+fn bitset_ops() {
+    let mut keys: KeySet = vec![EV_KEY::KEY_B, EV_KEY::KEY_A].into_iter().collect();
+    assert!(keys.contains(&EV_KEY::KEY_A));
+    assert!(!keys.insert(&EV_KEY::KEY_B));
+    assert!(keys.insert(&EV_KEY::KEY_MAX));
+    assert_eq!(keys.len(), 3);
+    assert!(keys.remove(&EV_KEY::KEY_A));
+    assert_eq!(keys.iter().collect::<Vec<_>>(), vec![EV_KEY::KEY_B, EV_KEY::KEY_MAX]);
+    keys.clear();
+    assert!(keys.is_empty());
+}
+
+#[test]
+fn bitset_set_operations() {
+    let a: AbsSet = vec![EV_ABS::ABS_X, EV_ABS::ABS_Y].into_iter().collect();
+    let b: AbsSet = vec![EV_ABS::ABS_Y, EV_ABS::ABS_PRESSURE].into_iter().collect();
+    assert_eq!((&a | &b).len(), 3);
+    assert_eq!((&a & &b).iter().collect::<Vec<_>>(), vec![EV_ABS::ABS_Y]);
+    assert_eq!((&a - &b).iter().collect::<Vec<_>>(), vec![EV_ABS::ABS_X]);
+    assert_eq!(&a ^ &b, vec![EV_ABS::ABS_X, EV_ABS::ABS_PRESSURE].into_iter().collect());
+    assert!((&a & &b).is_subset(&a) && a.is_superset(&(&a & &b)));
+    assert!(a.is_disjoint(&(&b - &a)));
+
+    let d = Device::new().unwrap();
+    d.enable(&EventCode::EV_KEY(EV_KEY::BTN_LEFT)).unwrap();
+    d.enable(&EventCode::EV_REL(EV_REL::REL_X)).unwrap();
+    d.enable(&InputProp::INPUT_PROP_POINTER).unwrap();
+    let types = d.supported_types();
+    assert!(types.contains(&EventType::EV_KEY) && types.contains(&EventType::EV_REL));
+    assert!(!types.contains(&EventType::EV_ABS));
+    assert_eq!(d.supported_keys().iter().collect::<Vec<_>>(), vec![EV_KEY::BTN_LEFT]);
+    assert_eq!(d.supported_rel().iter().collect::<Vec<_>>(), vec![EV_REL::REL_X]);
+    assert!(d.supported_abs().is_empty());
+    assert!(d.supported_properties().contains(&InputProp::INPUT_PROP_POINTER));
+}
+
+#[test]
+fn kernel_bulk_state() {
+    let uinput = UInputBuilder::new()
+        .name("evdev-rs bulk state test")
+        .code(&EventCode::EV_KEY(EV_KEY::KEY_A))
+        .code(&EventCode::EV_KEY(EV_KEY::KEY_B))
+        .code(&EventCode::EV_LED(EV_LED::LED_CAPSL))
+        .code(&EventCode::EV_SW(EV_SW::SW_LID))
+        .build()
+        .unwrap();
+
+    let f = File::open(uinput.devnode().unwrap()).unwrap();
+    let d = Device::new_from_fd(f).unwrap();
+    assert!(d.key_state().unwrap().is_empty());
+
+    let time = TimeVal::new(0, 0);
+    uinput.write_event(&InputEvent::new(&time, &EventCode::EV_KEY(EV_KEY::KEY_B), 1)).unwrap();
+    uinput.write_event(&InputEvent::new(&time, &EventCode::EV_LED(EV_LED::LED_CAPSL), 1)).unwrap();
+    uinput.write_event(&InputEvent::new(&time, &EventCode::EV_SW(EV_SW::SW_LID), 1)).unwrap();
+    uinput.write_event(&InputEvent::new(&time, &EventCode::EV_SYN(EV_SYN::SYN_REPORT), 0)).unwrap();
+
+    assert_eq!(d.key_state().unwrap().iter().collect::<Vec<_>>(), vec![EV_KEY::KEY_B]);
+    assert!(d.led_state().unwrap().contains(&EV_LED::LED_CAPSL));
+    assert!(d.switch_state().unwrap().contains(&EV_SW::SW_LID));
+    assert!(d.sound_state().unwrap().is_empty());
+    // libevdev has not read the events yet.
+    assert_eq!(d.event_value(&EventCode::EV_KEY(EV_KEY::KEY_B)), Some(0));
+}
+
+#[test]
+fn led_controller_sync() {
+    use evdev::led::LedController;
+    use std::fs::OpenOptions;
+
+    let keyboards: Vec<_> = (0..2).map(|i| {
+        UInputBuilder::new()
+            .name(&format!("evdev-rs led test {}", i))
+            .code(&EventCode::EV_KEY(EV_KEY::KEY_CAPSLOCK))
+            .code(&EventCode::EV_LED(EV_LED::LED_CAPSL))
+            .code(&EventCode::EV_LED(EV_LED::LED_NUML))
+            .build()
+            .unwrap()
+    }).collect();
+    let devices: Vec<_> = keyboards.iter().map(|uinput| {
+        let f = OpenOptions::new().read(true).write(true).open(uinput.devnode().unwrap()).unwrap();
+        Device::new_from_fd(f).unwrap()
+    }).collect();
+
+    devices[0].kernel_set_led_values(&[(EV_LED::LED_NUML, LedState::On),
+                                       (EV_LED::LED_CAPSL, LedState::Off)]).unwrap();
+    assert_eq!(devices[0].event_value(&EventCode::EV_LED(EV_LED::LED_NUML)), Some(1));
+    assert!(devices[0].kernel_set_led_values(&[(EV_LED::LED_KANA, LedState::On)]).is_err());
+
+    let mut leds = LedController::from_device(&devices[0]).unwrap();
+    let time = TimeVal::new(0, 0);
+    assert!(leds.process(&InputEvent::new(&time, &EventCode::EV_KEY(EV_KEY::KEY_CAPSLOCK), 1)));
+    assert!(!leds.process(&InputEvent::new(&time, &EventCode::EV_KEY(EV_KEY::KEY_CAPSLOCK), 0)));
+    for device in &devices {
+        leds.apply(device).unwrap();
+    }
+
+    for device in &devices {
+        let lit = device.led_state().unwrap();
+        assert!(lit.contains(&EV_LED::LED_NUML));
+        assert!(lit.contains(&EV_LED::LED_CAPSL));
+    }
+}
+
+#[test]
+fn device_log_handler() {
+    use evdev::logging::LogPriority;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    let records = Rc::new(RefCell::new(Vec::new()));
+    let mut d = Device::new_from_fd(File::open("/dev/input/event0").unwrap()).unwrap();
+    let sink = records.clone();
+    d.set_log_handler(LogPriority::Info, move |record| {
+        sink.borrow_mut().push((record.priority, record.function.to_string(),
+                                record.message.to_string()));
+    });
+
+    // libevdev reports calling set_fd twice as a bug of the caller.
+    assert!(d.set_fd(File::open("/dev/input/event0").unwrap()).is_err());
+    {
+        let records = records.borrow();
+        assert_eq!(records.len(), 1);
+        let (priority, ref function, ref message) = records[0];
+        assert_eq!(priority, LogPriority::Error);
+        assert_eq!(function, "libevdev_set_fd");
+        assert!(message.contains("already initialized"), "{}", message);
+        assert!(!message.ends_with('\n'));
+    }
+
+    d.clear_log_handler();
+    assert!(d.set_fd(File::open("/dev/input/event0").unwrap()).is_err());
+    assert_eq!(records.borrow().len(), 1);
+}
+
+#[test]
+fn global_log_handler_clears_itself() {
+    use evdev::logging;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+    logging::set_log_handler(move |_| {
+        counter.fetch_add(1, Ordering::SeqCst);
+        // Used to deadlock on the lock held while calling the handler.
+        logging::clear_log_handler();
+    });
+
+    let mut d = Device::new_from_fd(File::open("/dev/input/event0").unwrap()).unwrap();
+    assert!(d.set_fd(File::open("/dev/input/event0").unwrap()).is_err());
+    assert!(d.set_fd(File::open("/dev/input/event0").unwrap()).is_err());
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn typed_capabilities() {
+    let d = Device::new().unwrap();
+    let abs_x = EventCode::EV_ABS(EV_ABS::ABS_X);
+    d.enable_event_code(&abs_x, EnableData::Abs(AbsInfo {
+        value: 0, minimum: 0, maximum: 100, fuzz: 0, flat: 0, resolution: 0,
+    })).unwrap();
+    d.enable_event_code(&EventCode::EV_REP(EV_REP::REP_DELAY), EnableData::Rep(250)).unwrap();
+    d.enable(&InputProp::INPUT_PROP_DIRECT).unwrap();
+    assert!(d.has(&EventType::EV_ABS));
+    assert!(d.has(&abs_x));
+    assert!(d.has(&InputProp::INPUT_PROP_DIRECT));
+    assert_eq!(d.abs_info(&abs_x).map(|absinfo| absinfo.maximum), Some(100));
+    assert_eq!(d.event_value(&EventCode::EV_REP(EV_REP::REP_DELAY)), Some(250));
+
+    d.disable(&abs_x).unwrap();
+    assert!(!d.has(&abs_x));
+    d.disable(&EventType::EV_ABS).unwrap();
+    assert!(!d.has(&EventType::EV_ABS));
+}
+
+#[test]
+fn abs_getters_zero() {
+    let d = Device::new().unwrap();
+    d.enable_event_code(&EventCode::EV_ABS(EV_ABS::ABS_X), EnableData::Abs(AbsInfo {
+        value: 0, minimum: 0, maximum: 100, fuzz: 0, flat: 0, resolution: 0,
+    })).unwrap();
+
+    let abs_x = EV_ABS::ABS_X as u32;
+    assert_eq!(d.abs_minimum(abs_x), Ok(0));
+    assert_eq!(d.abs_maximum(abs_x), Ok(100));
+    assert_eq!(d.abs_fuzz(abs_x), Ok(0));
+    assert_eq!(d.abs_minimum(EV_ABS::ABS_Y as u32),
+               Err(Error::UnsupportedCapability("ABS_Y".to_string())));
+}
+
+#[test]
+fn error_variants() {
+    use std::io;
+
+    let d = Device::new().unwrap();
+    assert_eq!(d.disable(&InputProp::INPUT_PROP_POINTER), Err(Error::UnsupportedCapability(
+        "INPUT_PROP_POINTER".to_string())));
+    assert_eq!(d.enable_event_code(&EventCode::EV_ABS(EV_ABS::ABS_X), EnableData::None),
+               Err(Error::InvalidCode("ABS_X".to_string())));
+    assert_eq!(d.enable_event_code(&EventCode::EV_KEY(EV_KEY::KEY_A), EnableData::Rep(250)),
+               Err(Error::InvalidCode("KEY_A".to_string())));
+    match d.set_event_value(&EventCode::EV_KEY(EV_KEY::KEY_A), 1) {
+        Err(Error::UnsupportedCapability(code)) => assert_eq!(code, "KEY_A"),
+        result => panic!("unexpected {:?}", result),
+    }
+
+    let mut d = Device::new_from_fd(File::open("/dev/input/event0").unwrap()).unwrap();
+    match d.set_fd(File::open("/dev/input/event0").unwrap()) {
+        Err(error) => {
+            assert_eq!(error.errno(), Some(nix::errno::Errno::EBADF));
+            assert_eq!(error.path().unwrap().to_str(), Some("/dev/input/event0"));
+            assert!(error.to_string().starts_with("libevdev_set_fd on /dev/input/event0"));
+        },
+        Ok(()) => panic!("set_fd succeeded twice"),
+    }
+
+    let error: io::Error = Error::WouldBlock.into();
+    assert_eq!(error.kind(), io::ErrorKind::WouldBlock);
+}
+
+#[test]
+fn description_repeat_values() {
+    let d = Device::new().unwrap();
+    d.enable(&EventCode::EV_KEY(EV_KEY::KEY_A)).unwrap();
+    d.enable_event_code(&EventCode::EV_REP(EV_REP::REP_DELAY), EnableData::Rep(250)).unwrap();
+    d.enable_event_code(&EventCode::EV_REP(EV_REP::REP_PERIOD), EnableData::Rep(33)).unwrap();
+
+    let description = DeviceDescription::from_device(&d);
+    assert_eq!(description.state, vec![(EventCode::EV_REP(EV_REP::REP_DELAY), 250),
+                                       (EventCode::EV_REP(EV_REP::REP_PERIOD), 33)]);
+
+    let copy = description.to_device().unwrap();
+    assert_eq!(copy.event_value(&EventCode::EV_REP(EV_REP::REP_DELAY)), Some(250));
+    assert_eq!(copy.event_value(&EventCode::EV_REP(EV_REP::REP_PERIOD)), Some(33));
+    assert_eq!(DeviceDescription::from_device(&copy), description);
+}
+
+#[test]
+fn evemu_round_trip() {
+    use evdev::evemu;
+
+    let d = Device::new().unwrap();
+    d.set_name("evemu test device");
+    d.set_bustype(BusType::BUS_USB as u16);
+    d.set_vendor_id(0x46d);
+    d.set_product_id(0xc52b);
+    d.set_version(0x111);
+    d.enable(&EventCode::EV_KEY(EV_KEY::BTN_LEFT)).unwrap();
+    d.enable(&EventCode::EV_REL(EV_REL::REL_X)).unwrap();
+    d.enable(&EventCode::EV_LED(EV_LED::LED_CAPSL)).unwrap();
+    d.enable_event_code(&EventCode::EV_ABS(EV_ABS::ABS_X), EnableData::Abs(AbsInfo {
+        value: 0, minimum: -10, maximum: 1919, fuzz: 1, flat: 2, resolution: 30,
+    })).unwrap();
+    d.enable(&InputProp::INPUT_PROP_POINTER).unwrap();
+    d.set_event_value(&EventCode::EV_LED(EV_LED::LED_CAPSL), 1).unwrap();
+
+    let time = TimeVal::new(12, 345);
+    let recording = Recording {
+        description: DeviceDescription::from_device(&d),
+        events: vec![
+            InputEvent::new(&time, &EventCode::EV_REL(EV_REL::REL_X), -1),
+            InputEvent::new(&time, &EventCode::EV_UNK { event_type: 2, event_code: 0xe }, 3),
+            InputEvent::new(&time, &EventCode::EV_SYN(EV_SYN::SYN_REPORT), 0),
+        ],
+    };
+    assert!(recording.description.codes.contains(&EventCode::EV_ABS(EV_ABS::ABS_X)));
+    assert_eq!(recording.description.state, vec![(EventCode::EV_LED(EV_LED::LED_CAPSL), 1)]);
+
+    let mut text = Vec::new();
+    evemu::write(&mut text, &recording).unwrap();
+    let text = String::from_utf8(text).unwrap();
+    assert!(text.contains("I: 0003 046d c52b 0111\n"));
+    assert!(text.contains("A: 00 -10 1919 1 2 30\n"));
+    assert!(text.contains("L: 01 1\n"));
+    assert!(text.contains("E: 12.000345 0002 0000 -001\t"));
+
+    let parsed = evemu::parse(&text).unwrap();
+    assert_eq!(parsed, recording);
+    let mut again = Vec::new();
+    evemu::write(&mut again, &parsed).unwrap();
+    assert_eq!(String::from_utf8(again).unwrap(), text);
+
+    // Older recordings have no resolution, comments may follow any line.
+    let parsed = evemu::parse("# EVEMU 1.0\nN: old # device\nI: 0011 0001 0001 ab41\n\
+                               B: 00 0b 00 00 00 00 00 00 00\n\
+                               B: 03 01 00 00 00 00 00 00 00 # ABS_X\n\
+                               A: 00 0 100 0 0\n\
+                               E: 0.100000 0003 0000 0050\t# EV_ABS / ABS_X 50\n").unwrap();
+    assert_eq!(parsed.description.name, "old # device");
+    assert_eq!(parsed.description.id.bustype, BusType::BUS_I8042);
+    assert_eq!(parsed.description.types, vec![EventType::EV_SYN, EventType::EV_KEY, EventType::EV_ABS]);
+    assert_eq!(parsed.description.codes, vec![EventCode::EV_ABS(EV_ABS::ABS_X)]);
+    assert_eq!(parsed.description.absinfo[0].1.maximum, 100);
+    assert_eq!(parsed.events[0].time, TimeVal::new(0, 100000));
+
+    match evemu::parse("N: x\nX: 1\n") {
+        Err(Error::Parse { line: 2, .. }) => (),
+        result => panic!("unexpected {:?}", result),
+    }
+}
+
+#[test]
+fn libinput_record_round_trip() {
+    use evdev::libinput_record;
+
+    let d = Device::new().unwrap();
+    d.set_name("libinput \"test\" # device");
+    d.set_bustype(BusType::BUS_I8042 as u16);
+    d.set_vendor_id(2);
+    d.set_product_id(7);
+    d.set_version(0x1b1);
+    d.enable(&EventCode::EV_KEY(EV_KEY::BTN_TOUCH)).unwrap();
+    d.enable_event_code(&EventCode::EV_ABS(EV_ABS::ABS_MT_SLOT), EnableData::Abs(AbsInfo {
+        value: 0, minimum: 0, maximum: 4, fuzz: 0, flat: 0, resolution: 0,
+    })).unwrap();
+    d.enable_event_code(&EventCode::EV_ABS(EV_ABS::ABS_X), EnableData::Abs(AbsInfo {
+        value: 0, minimum: 1266, maximum: 5676, fuzz: 0, flat: 0, resolution: 45,
+    })).unwrap();
+    d.enable(&InputProp::INPUT_PROP_BUTTONPAD).unwrap();
+
+    let event = |sec, usec, code: EventCode, value| InputEvent::new(&TimeVal::new(sec, usec), &code, value);
+    let recording = Recording {
+        description: DeviceDescription::from_device(&d),
+        events: vec![
+            event(0, 0, EventCode::EV_ABS(EV_ABS::ABS_X), 2000),
+            event(0, 0, EventCode::EV_KEY(EV_KEY::BTN_TOUCH), 1),
+            event(0, 0, EventCode::EV_SYN(EV_SYN::SYN_REPORT), 0),
+            event(0, 12000, EventCode::EV_ABS(EV_ABS::ABS_X), 2010),
+            event(0, 12000, EventCode::EV_SYN(EV_SYN::SYN_REPORT), 0),
+        ],
+    };
+
+    let mut text = Vec::new();
+    libinput_record::write(&mut text, std::slice::from_ref(&recording)).unwrap();
+    let text = String::from_utf8(text).unwrap();
+    assert!(text.contains("    id: [17, 2, 7, 433]\n"));
+    assert!(text.contains("      0: [1266, 5676, 0, 0, 45]\n"));
+    assert!(text.contains("    properties: [2]\n"));
+    assert!(text.contains("    - [  0,  12000,   3,   0,    2010] # EV_ABS / ABS_X"));
+    assert!(text.contains("SYN_REPORT (0) ---------- +12ms\n"));
+
+    let parsed = libinput_record::parse(&text).unwrap();
+    assert_eq!(parsed, vec![recording]);
+
+    // Sections other than evdev, and libinput events, are skipped.
+    let parsed = libinput_record::parse("# libinput record\nversion: 1\nndevices: 2\n\
+                                         libinput:\n  version: \"1.19.0\"\n\
+                                         devices:\n\
+                                         - node: /dev/input/event5\n  evdev:\n\
+                                         \x20   # Name: pad\n    name: \"pad\"\n    id: [3, 1, 1, 1]\n\
+                                         \x20   codes:\n      0: [0, 1, 2, 3] # EV_SYN\n      3: [0, 999] # EV_ABS\n\
+                                         \x20   absinfo:\n      0: [0, 100, 0, 0, 10]\n    properties: []\n\
+                                         \x20 udev:\n    properties:\n    - ID_INPUT=1\n\
+                                         \x20 events:\n  # Current time is 12:00:00\n\
+                                         \x20 - evdev:\n    - [  0,      0,   3,   0,   50] # EV_ABS / ABS_X\n\
+                                         \x20 - libinput:\n    - {time: 0.0, type: POINTER_MOTION}\n\
+                                         - node: /dev/input/event6\n  evdev:\n    name: \"keyboard\"\n\
+                                         \x20   id: [17, 1, 1, 1]\n").unwrap();
+    assert_eq!(parsed.len(), 2);
+    assert_eq!(parsed[0].description.name, "pad");
+    assert_eq!(parsed[0].description.id.bustype, BusType::BUS_USB);
+    assert_eq!(parsed[0].description.types, vec![EventType::EV_SYN, EventType::EV_ABS]);
+    assert_eq!(parsed[0].description.codes, vec![EventCode::EV_ABS(EV_ABS::ABS_X)]);
+    assert_eq!(parsed[0].description.absinfo[0].1.resolution, 10);
+    assert!(parsed[0].description.properties.is_empty());
+    assert_eq!(parsed[0].events, vec![event(0, 0, EventCode::EV_ABS(EV_ABS::ABS_X), 50)]);
+    assert_eq!(parsed[1].description.name, "keyboard");
+
+    match libinput_record::parse("devices:\n- evdev:\n    id: [1, 2]\n") {
+        Err(Error::Parse { line: 3, .. }) => (),
+        result => panic!("unexpected {:?}", result),
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_bitset_unknown_codes() {
+    let d = Device::new().unwrap();
+    d.enable(&EventCode::EV_REL(EV_REL::REL_X)).unwrap();
+    d.enable(&EventCode::EV_UNK { event_type: 2, event_code: 0xe }).unwrap();
+    let rel = d.supported_rel();
+    assert_eq!(rel.len(), 2);
+
+    // Only the known codes are written, and the length written says so.
+    let bytes = bincode::serialize(&rel).unwrap();
+    let parsed: RelSet = bincode::deserialize(&bytes).unwrap();
+    assert_eq!(parsed.iter().collect::<Vec<_>>(), vec![EV_REL::REL_X]);
+    assert_eq!(serde_json::to_string(&rel).unwrap(), r#"["REL_X"]"#);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_names() {
+    let time = TimeVal::new(1, 2);
+    let event = InputEvent::new(&time, &EventCode::EV_KEY(EV_KEY::KEY_A), 1);
+    let json = serde_json::to_string(&event).unwrap();
+    assert_eq!(json, r#"{"time":{"tv_sec":1,"tv_usec":2},"event_type":"EV_KEY","event_code":"KEY_A","value":1}"#);
+    assert_eq!(serde_json::from_str::<InputEvent>(&json).unwrap(), event);
+
+    // Codes without a name are written as numbers.
+    let unknown = EventCode::EV_UNK { event_type: 2, event_code: 0xe };
+    assert_eq!(serde_json::to_string(&unknown).unwrap(), "[2,14]");
+    assert_eq!(serde_json::from_str::<EventCode>("[2,14]").unwrap(), unknown);
+    assert_eq!(serde_json::from_str::<EventCode>("\"ABS_MT_SLOT\"").unwrap(),
+               EventCode::EV_ABS(EV_ABS::ABS_MT_SLOT));
+    assert_eq!(serde_json::from_str::<EV_REL>("\"REL_WHEEL\"").unwrap(), EV_REL::REL_WHEEL);
+    assert_eq!(serde_json::from_str::<EV_REL>("8").unwrap(), EV_REL::REL_WHEEL);
+    assert_eq!(serde_json::to_string(&BusType::BUS_USB).unwrap(), "\"BUS_USB\"");
+    assert_eq!(serde_json::to_string(&InputProp::INPUT_PROP_POINTER).unwrap(), "\"INPUT_PROP_POINTER\"");
+    assert!(serde_json::from_str::<EventCode>("\"KEY_NOPE\"").is_err());
+    assert!(serde_json::from_str::<EV_KEY>("\"REL_X\"").is_err());
+
+    let d = Device::new().unwrap();
+    d.set_name("serde test device");
+    d.set_bustype(BusType::BUS_BLUETOOTH as u16);
+    d.enable(&EventCode::EV_KEY(EV_KEY::BTN_LEFT)).unwrap();
+    d.enable_event_code(&EventCode::EV_ABS(EV_ABS::ABS_X), EnableData::Abs(AbsInfo {
+        value: 0, minimum: 0, maximum: 100, fuzz: 0, flat: 0, resolution: 3,
+    })).unwrap();
+    d.enable(&InputProp::INPUT_PROP_DIRECT).unwrap();
+    let description = DeviceDescription::from_device(&d);
+    let json = serde_json::to_string(&description).unwrap();
+    assert!(json.contains(r#""codes":["BTN_LEFT","ABS_X"]"#));
+    assert_eq!(serde_json::from_str::<DeviceDescription>(&json).unwrap(), description);
+}
+
+#[test]
+fn player_replays_recording() {
+    use evdev::player::Player;
+    use std::fs::OpenOptions;
+    use std::os::unix::fs::OpenOptionsExt;
+    use std::time::{Duration, Instant};
+
+    let d = Device::new().unwrap();
+    d.set_name("player test device");
+    d.enable(&EventCode::EV_KEY(EV_KEY::KEY_A)).unwrap();
+
+    let at = |usec| TimeVal::new(100, usec);
+    let syn = EventCode::EV_SYN(EV_SYN::SYN_REPORT);
+    let recording = Recording {
+        description: DeviceDescription::from_device(&d),
+        events: vec![
+            InputEvent::new(&at(0), &EventCode::EV_KEY(EV_KEY::KEY_A), 1),
+            InputEvent::new(&at(0), &syn, 0),
+            InputEvent::new(&at(200_000), &EventCode::EV_KEY(EV_KEY::KEY_A), 0),
+            InputEvent::new(&at(200_000), &syn, 0),
+        ],
+    };
+
+    let mut player = Player::new(&recording).unwrap();
+    let f = OpenOptions::new()
+        .read(true)
+        .custom_flags(nix::libc::O_NONBLOCK)
+        .open(player.device().devnode().unwrap())
+        .unwrap();
+    let replayed = Device::new_from_fd(f).unwrap();
+
+    // The first frame is due right away, the second one 200ms later at
+    // normal speed and 100ms later at twice the speed.
+    let now = Instant::now();
+    assert_eq!(player.poll(now).unwrap(), Some(now + Duration::from_millis(200)));
+    assert_eq!(player.position(), 2);
+    player.set_speed(2.0);
+    assert_eq!(player.poll(now).unwrap(), Some(now + Duration::from_millis(100)));
+    assert_eq!(player.position(), 2);
+
+    player.pause();
+    assert_eq!(player.poll(now + Duration::from_secs(1)).unwrap(), None);
+    assert_eq!(player.position(), 2);
+    player.resume();
+    // The timing starts over after a pause.
+    assert_eq!(player.poll(now + Duration::from_secs(1)).unwrap(), None);
+    assert!(player.is_finished());
+
+    player.seek(0);
+    let start = Instant::now();
+    player.run().unwrap();
+    assert!(start.elapsed() >= Duration::from_millis(100));
+
+    let frames: Vec<_> = replayed.frames().map(|frame| frame.unwrap()).collect();
+    let values: Vec<_> = frames.iter().map(|frame| frame.events[0].value).collect();
+    assert_eq!(values, vec![1, 0, 1, 0]);
+
+    player.seek_time(Duration::from_millis(150));
+    assert_eq!(player.position(), 2);
+}
+
+#[test]
+fn player_loops_single_event() {
+    use evdev::player::Player;
+    use std::time::Instant;
+
+    let d = Device::new().unwrap();
+    d.set_name("player loop test device");
+    d.enable(&EventCode::EV_KEY(EV_KEY::KEY_A)).unwrap();
+
+    let recording = Recording {
+        description: DeviceDescription::from_device(&d),
+        events: vec![
+            InputEvent::new(&TimeVal::new(100, 0), &EventCode::EV_SYN(EV_SYN::SYN_REPORT), 0),
+        ],
+    };
+
+    // Without a duration to loop over, the recording is played once.
+    let mut player = Player::new(&recording).unwrap();
+    player.set_looping(true);
+    assert_eq!(player.poll(Instant::now()).unwrap(), None);
+    assert!(player.is_finished());
+    player.seek(0);
+    player.run().unwrap();
+    assert_eq!(player.position(), 1);
+}
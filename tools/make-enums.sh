@@ -1,5 +1,9 @@
 #!/usr/bin/env bash
 
+# Regenerates the bundled src/enums.rs from the headers of the libevdev
+# submodule, with the generator of build.rs.
+
 set -eux
 
-./tools/make-event-names.py evdev-sys/libevdev/include/linux/input-event-codes.h evdev-sys/libevdev/include/linux/input.hpython tools/make-event-names.py evdev-sys/libevdev/include/linux/input-event-codes.h evdev-sys/libevdev/include/linux/input.h > src/enums.rs
+EVDEV_INPUT_HEADERS=evdev-sys/libevdev/include EVDEV_ENUMS_OUT="$PWD/src/enums.rs" \
+    cargo build --features generate-enums